@@ -5,7 +5,7 @@ use r_port_doctor::tools::dns_lookup::resolve_socket_table_addresses;
 use r_port_doctor::tools::get_sockets::get_sockets;
 use r_port_doctor::tools::rpderror::RpdError;
 use r_port_doctor::tools::socket::{Socket};
-use r_port_doctor::tools::print::print_socket_stats;
+use r_port_doctor::tools::print::{print_socket_stats, print_socket_tree};
 fn main() {     
     #[cfg(windows)]
     {
@@ -32,15 +32,15 @@ fn main() {
 
     Socket::filter_socket_table(&mut sockets, &args, argc);
 
-    if args.resolve_hostname {
-        if !matches!(&args.mode, Some(m) if m.to_lowercase() == "udp") {
-            resolve_socket_table_addresses(&mut sockets);
-        }
+    if args.resolve_hostname && !matches!(&args.mode, Some(m) if m.to_lowercase() == "udp") {
+        resolve_socket_table_addresses(&mut sockets);
     }
 
     Socket::sort_socket_table(&mut sockets, &args);
     if args.stats {
         print_socket_stats(&sockets);
+    } else if args.tree {
+        print_socket_tree(&sockets, &args);
     } else {
         Socket::print_socket_table(&sockets, &args);
     }
@@ -1,14 +1,44 @@
 pub mod socket;
 pub mod print;
+#[cfg(windows)]
 pub mod nt_to_dos;
 pub mod args;
+#[cfg(windows)]
 pub mod udp_table;
+#[cfg(windows)]
 pub mod tcp_table;
+#[cfg(windows)]
 pub mod get_process_name;
+#[cfg(windows)]
 pub mod get_uptime;
+#[cfg(windows)]
+pub mod get_process_info;
+#[cfg(target_os = "linux")]
+pub mod proc_net;
+#[cfg(target_os = "linux")]
+pub mod sock_diag;
+#[cfg(target_os = "linux")]
+pub mod netns;
+#[cfg(target_os = "linux")]
+pub mod cgroup;
+#[cfg(target_os = "linux")]
+pub mod systemd_socket;
 pub mod dns_lookup;
 pub mod get_sockets;
+pub mod socket_source;
+#[cfg(any(windows, target_os = "linux"))]
+pub mod process_info;
+pub mod process_tree;
+pub mod input;
+pub mod civil_date;
+pub mod netstat_input;
+pub mod lsof_input;
+pub mod powershell_input;
+pub mod pcap_input;
+pub mod zeek_input;
+pub mod socket_filter;
 pub mod range_filter;
+pub mod where_filter;
 pub mod stats;
 pub mod print_utils;
 pub mod connection_state_map;
@@ -1,36 +1,92 @@
-use clap::{arg, Parser};
+use clap::Parser;
 use crate::tools::{print_utils::OUTPUT_FIELDS, rpderror::RpdError};
 
 #[derive(Parser, Debug)]
 #[command(name = "r-port-doctor", version, about = "Port debug and diagnostic tool")]
 pub struct Args {
-    #[arg(short = 'l', long, help = "Filter by local port number")]
-    pub port: Option<u16>,
+    #[arg(short = 'l', long, help = "Filter by local port number, or comma-separated list of ports and ranges (e.g., 80,443,8000-8100)", value_name = "PORTS")]
+    pub port: Option<String>,
 
-    #[arg(short = 'r', long = "remote-port", help = "Filter by remote port number")]
-    pub remote_port: Option<u16>,
+    #[arg(long = "exclude-port", help = "Exclude local port numbers, given as a comma-separated list of ports and ranges (e.g., 137-139,5353)", value_name = "PORTS")]
+    pub exclude_port: Option<String>,
 
-    #[arg(short = 'm', long, help = "Filter by protocol (TCP or UDP)")]
+    #[arg(short = 'r', long = "remote-port", help = "Filter by remote port number, or comma-separated list of ports and ranges (e.g., 80,443,8000-8100)", value_name = "PORTS")]
+    pub remote_port: Option<String>,
+
+    #[arg(short = 'm', long, help = "Filter by protocol (TCP, UDP, UNIX, RAW or PACKET)")]
     pub mode: Option<String>,
 
     #[arg(short = 'n', long = "process-name", help = "Filter by process name (regular expression)")]
     pub process_name: Option<String>,
 
-    #[arg(short = 'i', long, help = "Filter by process ID (PID)")]
-    pub pid: Option<u32>,
+    #[arg(long = "exclude-process", help = "Exclude processes by name, given as a comma-separated list of regular expressions matched like --process-name (e.g., chrome,firefox,svchost)", value_name = "NAMES")]
+    pub exclude_process: Option<String>,
+
+    #[arg(long = "peer-process", help = "Filter Unix sockets by the process name of their connected peer (regular expression)")]
+    pub peer_process: Option<String>,
+
+    #[arg(long = "container", help = "Filter by the container of the owning process, matching its ID, runtime or pod UID (regular expression, Linux)")]
+    pub container: Option<String>,
+
+    #[arg(long = "unit", help = "Filter by the systemd unit or slice of the owning process (regular expression, Linux)")]
+    pub unit: Option<String>,
+
+    #[arg(long = "user", help = "Filter by the user owning the process (regular expression)")]
+    pub user: Option<String>,
+
+    #[arg(long = "cmdline", help = "Filter by the full command line of the owning process (regular expression)")]
+    pub cmdline: Option<String>,
 
-    #[arg(short = 's', long, help = "Filter by connection state (e.g., LISTEN, ESTABLISHED)")]
+    #[arg(short = 'i', long, help = "Filter by process ID (PID), or comma-separated list of PIDs and ranges (e.g., 1200,3100-3200)", value_name = "PIDS")]
+    pub pid: Option<String>,
+
+    #[arg(long = "pid-tree", help = "Filter by process ID (PID), including every descendant of the process", value_name = "PID", conflicts_with = "pid")]
+    pub pid_tree: Option<u32>,
+
+    #[arg(short = 's', long, help = "Filter by connection state, or comma-separated list of states (e.g., LISTEN or ESTABLISHED,CLOSE_WAIT)", value_name = "STATES")]
     pub state: Option<String>,
 
+    #[arg(long = "exclude-state", help = "Exclude connection states, given as a comma-separated list (e.g., TIME_WAIT,CLOSE_WAIT)", value_name = "STATES")]
+    pub exclude_state: Option<String>,
+
     #[arg(short = 'v', long = "ip-version", help = "Specify IP version 4 (IPv4) or 6 (IPv6). Defaults to IPv4.")]
     pub ip_version: Option<u8>,
 
-    #[arg(long = "local-address", help = "Filter by local IP address")]
+    #[arg(long = "local-address", help = "Filter by local IP address, CIDR prefix (e.g., 10.0.0.0/8) or comma-separated list of them")]
     pub local_address: Option<String>,
 
-    #[arg(long = "remote-address", help = "Filter by remote IP address")]
+    #[arg(long = "remote-address", help = "Filter by remote IP address, CIDR prefix (e.g., 10.0.0.0/8) or comma-separated list of them")]
     pub remote_address: Option<String>,
 
+    #[arg(long = "source", help = "Specify where sockets are collected from:
+    - iphelper (Windows IP Helper API, default on Windows)
+    - netlink (Linux sock_diag, includes TCP internals, default on Linux)
+    - procfs (Linux /proc/net tables)")]
+    pub source: Option<String>,
+
+    #[arg(long = "input", help = "Read the socket table from a file previously saved with '--json' instead of the system ('-' reads from stdin)", value_name = "FILE", conflicts_with = "source")]
+    pub input: Option<String>,
+
+    #[arg(long = "input-format", help = "Format of the '--input' file
+Available formats:
+    - json (table saved with '--json', default)
+    - netstat (output of 'netstat -ano' on Windows or 'netstat -tanup' on Linux)
+    - ss (output of 'ss -tanup')
+    - lsof (output of 'lsof -i -n -P -F pcnPtT')
+    - powershell (Get-NetTCPConnection or Get-NetUDPEndpoint exported with ConvertTo-Json or Export-Csv)
+    - pcap (pcap or pcapng capture, connections are reconstructed from the captured packets)
+    - zeek (Zeek conn.log, TSV or JSON)", value_name = "FORMAT", requires = "input")]
+    pub input_format: Option<String>,
+
+    #[arg(long = "process-map", help = "Socket table saved with '--json' on a captured host, used to name the processes of connections reconstructed from a capture", value_name = "FILE", requires = "input")]
+    pub process_map: Option<String>,
+
+    #[arg(long = "all-netns", help = "List sockets from every network namespace found in /proc/<pid>/ns/net instead of only our own (Linux)", conflicts_with = "input")]
+    pub all_netns: bool,
+
+    #[arg(long = "netns", help = "Filter by network namespace, given either as the namespace inode or the PID of a process inside it (Linux)", value_name = "INODE|PID")]
+    pub netns: Option<u64>,
+
     #[arg(long = "json", help = "Output results in JSON format")]
     pub json: bool,
 
@@ -45,13 +101,30 @@ Available fields:
     - process-name
     - uptime
     - local-address
-    - remote-address",
+    - remote-address
+    - rtt
+    - rtt-var
+    - cwnd
+    - retransmits
+    - mss
+    - congestion
+    - skmem
+    - netns
+    - container
+    - unit
+    - orig-bytes
+    - resp-bytes
+    - recv-q
+    - send-q",
     value_names = ["ORDER", "FIELD"], num_args = 2)]
     pub sort_by: Option<Vec<String>>,
 
     #[arg(long = "resolve-hostname", help = "Resolve remote IP addresses to hostnames using DNS (may take a few seconds for IPv4 addresses)")]
     pub resolve_hostname: bool,
 
+    #[arg(long = "tree", help = "Nest sockets under the ancestry of their owning process instead of printing a table", conflicts_with_all = ["json", "stats"])]
+    pub tree: bool,
+
     #[arg(long = "compact", help = "Removes table borders from output")]
     pub compact: bool,
 
@@ -61,7 +134,7 @@ Available fields:
     #[arg(long = "younger-than", help = "Filter connections by uptime being younger than provided seconds", value_name = "SECONDS")]
     pub younger_than: Option<u32>,
 
-    #[arg(long = "fields", help = "Show only the specified fields in the table (all shown by default)", value_name = "FIELD", num_args = 1..=8)]
+    #[arg(long = "fields", help = "Show only the specified fields in the table (all shown by default)", value_name = "FIELD", num_args = 1..=OUTPUT_FIELDS.len())]
     pub fields: Option<Vec<String>>,
 
     #[arg(long = "uptime", help = "Specify uptime format:
@@ -72,17 +145,35 @@ Available fields:
     - seconds")]
     pub uptime_format: Option<String>,
 
-    #[arg(long = "range", help = "Filter rows by value ranges of the specified field
+    #[arg(long = "range", help = "Filter rows by value ranges of the specified field, '-' leaves a bound open (e.g., --range uptime 3600 -)
+Can be repeated, rows must then be within every range
 Available fields:
     - pid
     - port  
     - remote-port
     - uptime (uses seconds)
     - local-address
-    - remote-address",
-    value_names = ["FIELD", "MIN", "MAX"], num_args = 3)]
+    - remote-address
+    - rtt (microseconds)
+    - rtt-var (microseconds)
+    - cwnd
+    - retransmits
+    - mss
+    - skmem (allocated bytes)
+    - orig-bytes
+    - resp-bytes
+    - recv-q (bytes)
+    - send-q (bytes)",
+    value_names = ["FIELD", "MIN", "MAX"], num_args = 3, action = clap::ArgAction::Append)]
     pub range: Option<Vec<String>>,
 
+    #[arg(long = "where", help = "Filter rows with an expression over the socket fields, combined with the other filters
+Comparisons: =, !=, <, <=, >, >=, ~ (regular expression), !~, in (list or range), not in
+Combined with 'and', 'or', 'not' and parentheses, e.g.:
+    \"port in (80, 443) and not process-name ~ chrome and state = established\"
+    \"remote-address in 10.0.0.0..10.255.255.255 or recv-q > 0\"", value_name = "EXPRESSION")]
+    pub where_expr: Option<String>,
+
     #[arg(long = "stats", help = "Outputs various statistics related to the socket table instead of the table itself")]
     pub stats: bool,
 
@@ -111,16 +202,37 @@ To get a value: --get <KEY> (e.g, --get port)",
 impl Args {
     pub fn get_argc(&self) -> usize {
         self.port.is_some() as usize + 
+        self.exclude_port.is_some() as usize +
         self.remote_port.is_some() as usize +
         self.process_name.is_some() as usize +
+        self.exclude_process.is_some() as usize +
+        self.peer_process.is_some() as usize +
+        self.container.is_some() as usize +
+        self.unit.is_some() as usize +
+        self.user.is_some() as usize +
+        self.cmdline.is_some() as usize +
         self.pid.is_some() as usize +
+        self.pid_tree.is_some() as usize +
         self.state.is_some() as usize +
+        self.exclude_state.is_some() as usize +
         self.local_address.is_some() as usize +
         self.remote_address.is_some() as usize +
+        self.netns.is_some() as usize +
         self.no_system as usize +
         self.older_than.is_some() as usize +
         self.younger_than.is_some() as usize +
-        self.range.is_some() as usize
+        self.get_ranges().count() +
+        self.where_expr.is_some() as usize
+    }
+
+    // Values of every '--range' occurrence are appended to the same list, three by three
+    pub fn get_ranges(&self) -> impl Iterator<Item = &[String]> {
+        self.range.iter().flat_map(|range| range.chunks(3))
+    }
+
+    // Imported tables and fixtures describe processes that may not exist on this machine
+    pub fn reads_live_system(&self) -> bool {
+        self.input.is_none() && !matches!(&self.source, Some(source) if source.eq_ignore_ascii_case("fixture"))
     }
 }
 
@@ -0,0 +1,191 @@
+use std::{collections::HashMap, fs};
+use crate::tools::socket::{Container, Socket};
+
+// Scope prefixes used by each runtime for the cgroup of a container, e.g. 'docker-<id>.scope' under the systemd cgroup driver
+const RUNTIME_PREFIXES: [(&str, &str); 4] = [("docker-", "docker"), ("libpod-", "podman"), ("cri-containerd-", "containerd"), ("crio-", "cri-o")];
+
+// Unit types systemd places processes in, slices only group other units
+const UNIT_SUFFIXES: [&str; 5] = [".service", ".scope", ".socket", ".mount", ".swap"];
+
+pub struct CgroupInfo {
+    pub container: Option<Container>,
+    pub unit: Option<String>,
+    pub slice: Option<String>
+}
+
+// Each line of /proc/<pid>/cgroup is 'hierarchy-ID:controllers:path', the unified hierarchy (cgroup v2) has no controllers
+pub fn get_cgroups(pid: u32) -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(format!("/proc/{}/cgroup", pid)) else { return Vec::new() };
+    parse_cgroup_lines(&content)
+}
+
+fn parse_cgroup_lines(content: &str) -> Vec<(String, String)> {
+    content.lines()
+        .filter_map(|line| {
+            let mut columns = line.splitn(3, ':').skip(1);
+            Some((columns.next()?.to_string(), columns.next()?.to_string()))
+        })
+        .collect()
+}
+
+fn is_container_id(id: &str) -> bool {
+    id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// 'pod<uid>' with cgroup v1, 'kubepods-<qos>-pod<uid>.slice' with the dashes of the UID replaced by underscores under systemd
+fn parse_pod_uid(segment: &str) -> Option<String> {
+    let name = segment.strip_suffix(".slice").unwrap_or(segment);
+    let uid = match name.strip_prefix("pod") {
+        Some(uid) => uid,
+        None => name.rsplit_once("-pod")?.1
+    };
+    if uid.is_empty() { None } else { Some(uid.replace('_', "-")) }
+}
+
+pub fn parse_container(path: &str) -> Option<Container> {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let pod_uid = segments.iter()
+        .take_while(|s| !s.ends_with(".scope"))
+        .filter(|s| s.starts_with("kubepods") || s.starts_with("pod"))
+        .find_map(|s| parse_pod_uid(s));
+
+    for (i, segment) in segments.iter().enumerate().rev() {
+        let name = segment.strip_suffix(".scope").unwrap_or(segment);
+        let parent = i.checked_sub(1).and_then(|j| segments.get(j)).copied();
+        let prefixed = RUNTIME_PREFIXES.iter()
+            .find_map(|(prefix, runtime)| name.strip_prefix(prefix).filter(|id| is_container_id(id)).map(|id| (id, *runtime)));
+        let (id, runtime) = match (prefixed, parent) {
+            (Some(prefixed), _) => prefixed,
+            (None, Some("docker")) if is_container_id(name) => (name, "docker"),
+            (None, _) if is_container_id(name) && pod_uid.is_some() => (name, "kubernetes"),
+            (None, Some("lxc")) => (name, "lxc"),
+            (None, _) => match name.strip_prefix("lxc.payload.") {
+                Some(lxc_name) => (lxc_name, "lxc"),
+                None => continue
+            }
+        };
+        return Some(Container {
+            id: id.to_string(),
+            runtime: runtime.to_string(),
+            pod_uid: pod_uid.clone()
+        })
+    }
+    None
+}
+
+// The innermost unit in the path owns the process, e.g. 'session-2.scope' in '/user.slice/user-1000.slice/session-2.scope'
+pub fn parse_systemd_unit(path: &str) -> (Option<String>, Option<String>) {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let unit_idx = segments.iter().rposition(|s| UNIT_SUFFIXES.iter().any(|suffix| s.ends_with(suffix)));
+    let slice = segments[..unit_idx.unwrap_or(segments.len())].iter()
+        .rev()
+        .find(|s| s.ends_with(".slice"))
+        .map(|s| s.to_string());
+    (unit_idx.map(|i| segments[i].to_string()), slice)
+}
+
+// systemd tracks units on the unified hierarchy, or on its own 'name=systemd' hierarchy on cgroup v1 hosts
+pub fn get_cgroup_info(pid: u32) -> CgroupInfo {
+    parse_cgroup_info(&get_cgroups(pid))
+}
+
+fn parse_cgroup_info(cgroups: &[(String, String)]) -> CgroupInfo {
+    let (unit, slice) = cgroups.iter()
+        .find(|(controllers, _)| controllers.is_empty() || controllers == "name=systemd")
+        .map_or((None, None), |(_, path)| parse_systemd_unit(path));
+    CgroupInfo {
+        container: cgroups.iter().find_map(|(_, path)| parse_container(path)),
+        unit,
+        slice
+    }
+}
+
+// Sockets of the same process share its cgroup, so each PID is only read once
+pub fn attach_cgroup_info(sockets: &mut [Socket]) {
+    let mut cgroup_infos: HashMap<u32, CgroupInfo> = HashMap::new();
+    for socket in sockets.iter_mut().filter(|s| s.pid != 0) {
+        let info = cgroup_infos.entry(socket.pid).or_insert_with(|| get_cgroup_info(socket.pid));
+        socket.container = info.container.clone();
+        socket.unit = info.unit.clone();
+        socket.slice = info.slice.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCKER_ID: &str = "3f2a9c1b7e4d5a6b8c9d0e1f2a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d";
+
+    fn container(path: &str) -> Option<(String, String, Option<String>)> {
+        parse_container(path).map(|c| (c.id, c.runtime, c.pod_uid))
+    }
+
+    #[test]
+    fn reads_paths_of_every_hierarchy() {
+        let content = format!("12:memory:/docker/{}\n1:name=systemd:/docker/{}\n0::/system.slice/docker-{}.scope\n", DOCKER_ID, DOCKER_ID, DOCKER_ID);
+        let cgroups = parse_cgroup_lines(&content);
+        assert_eq!(cgroups.len(), 3);
+        assert_eq!(cgroups[0], ("memory".to_string(), format!("/docker/{}", DOCKER_ID)));
+        assert_eq!(cgroups[2], (String::new(), format!("/system.slice/docker-{}.scope", DOCKER_ID)));
+    }
+
+    #[test]
+    fn parses_docker_and_podman_scopes() {
+        let docker = Some((DOCKER_ID.to_string(), "docker".to_string(), None));
+        assert_eq!(container(&format!("/system.slice/docker-{}.scope", DOCKER_ID)), docker);
+        assert_eq!(container(&format!("/docker/{}", DOCKER_ID)), docker);
+        assert_eq!(container(&format!("/user.slice/user-1000.slice/user@1000.service/user.slice/libpod-{}.scope/container", DOCKER_ID)).map(|c| c.1), Some("podman".to_string()));
+    }
+
+    #[test]
+    fn parses_kubernetes_pods() {
+        let systemd = format!("/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod5f1c2b7e_0a9d_4c3b_8e6f_1a2b3c4d5e6f.slice/cri-containerd-{}.scope", DOCKER_ID);
+        assert_eq!(container(&systemd), Some((DOCKER_ID.to_string(), "containerd".to_string(), Some("5f1c2b7e-0a9d-4c3b-8e6f-1a2b3c4d5e6f".to_string()))));
+        let cgroupfs = format!("/kubepods/besteffort/pod5f1c2b7e-0a9d-4c3b-8e6f-1a2b3c4d5e6f/{}", DOCKER_ID);
+        assert_eq!(container(&cgroupfs), Some((DOCKER_ID.to_string(), "kubernetes".to_string(), Some("5f1c2b7e-0a9d-4c3b-8e6f-1a2b3c4d5e6f".to_string()))));
+    }
+
+    #[test]
+    fn parses_lxc_containers() {
+        assert_eq!(container("/lxc.payload.web/system.slice").map(|c| (c.0, c.1)), Some(("web".to_string(), "lxc".to_string())));
+        assert_eq!(container("/lxc/web").map(|c| (c.0, c.1)), Some(("web".to_string(), "lxc".to_string())));
+    }
+
+    #[test]
+    fn leaves_host_processes_without_container() {
+        assert_eq!(container("/user.slice/user-1000.slice/session-2.scope"), None);
+        assert_eq!(container("/system.slice/nginx.service"), None);
+        assert_eq!(container("/init.scope"), None);
+        // Not a full container ID
+        assert_eq!(container("/system.slice/docker-3f2a9c1b.scope"), None);
+    }
+
+    #[test]
+    fn parses_innermost_unit_and_its_slice() {
+        let unit = |path: &str| {
+            let (unit, slice) = parse_systemd_unit(path);
+            (unit.unwrap_or_default(), slice.unwrap_or_default())
+        };
+        assert_eq!(unit("/user.slice/user-1000.slice/session-2.scope"), ("session-2.scope".to_string(), "user-1000.slice".to_string()));
+        assert_eq!(unit("/system.slice/nginx.service"), ("nginx.service".to_string(), "system.slice".to_string()));
+        assert_eq!(unit("/user.slice/user-1000.slice/user@1000.service/app.slice/app-gnome-firefox-4242.scope"), ("app-gnome-firefox-4242.scope".to_string(), "app.slice".to_string()));
+        assert_eq!(unit("/init.scope"), ("init.scope".to_string(), String::new()));
+        assert_eq!(unit("/system.slice"), (String::new(), "system.slice".to_string()));
+        assert_eq!(unit("/"), (String::new(), String::new()));
+    }
+
+    #[test]
+    fn reads_unit_from_systemd_hierarchy() {
+        // cgroup v1 host, the memory controller is grouped differently than systemd tracks units
+        let content = "12:memory:/system.slice\n3:cpu,cpuacct:/system.slice/sshd.service\n1:name=systemd:/system.slice/sshd.service\n";
+        let info = parse_cgroup_info(&parse_cgroup_lines(content));
+        assert_eq!((info.unit.as_deref(), info.slice.as_deref()), (Some("sshd.service"), Some("system.slice")));
+        assert!(info.container.is_none());
+
+        let content = format!("0::/system.slice/docker-{}.scope\n", DOCKER_ID);
+        let info = parse_cgroup_info(&parse_cgroup_lines(&content));
+        assert_eq!((info.unit, info.slice), (Some(format!("docker-{}.scope", DOCKER_ID)), Some("system.slice".to_string())));
+        assert_eq!(info.container.map(|c| c.runtime), Some("docker".to_string()));
+    }
+}
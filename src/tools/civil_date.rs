@@ -0,0 +1,68 @@
+// Conversions between days since 1970-01-01 and proleptic Gregorian dates (year, month, day), used for timestamps given or shown in UTC
+
+pub fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (days since 1970-01-01, year, month, day)
+    const KNOWN_DATES: [(i64, i64, i64, i64); 10] = [
+        (-719468, 0, 3, 1),
+        (-25567, 1900, 1, 1),
+        (-1, 1969, 12, 31),
+        (0, 1970, 1, 1),
+        (59, 1970, 3, 1),
+        (10957, 2000, 1, 1),
+        (11016, 2000, 2, 29),
+        (11017, 2000, 3, 1),
+        (19782, 2024, 2, 29),
+        (24855, 2038, 1, 19)
+    ];
+
+    #[test]
+    fn converts_known_dates_to_days() {
+        for (days, year, month, day) in KNOWN_DATES {
+            assert_eq!(days_from_civil(year, month, day), days, "{}-{}-{}", year, month, day);
+        }
+    }
+
+    #[test]
+    fn converts_known_days_to_dates() {
+        for (days, year, month, day) in KNOWN_DATES {
+            assert_eq!(civil_from_days(days), (year, month, day), "{}", days);
+        }
+    }
+
+    #[test]
+    fn round_trips_every_day_of_a_leap_cycle() {
+        for days in days_from_civil(1896, 1, 1)..days_from_civil(2104, 12, 31) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+        // 1900 and 2100 are not leap years, 2000 is
+        assert_eq!(civil_from_days(days_from_civil(1900, 2, 28) + 1), (1900, 3, 1));
+        assert_eq!(civil_from_days(days_from_civil(2100, 2, 28) + 1), (2100, 3, 1));
+        assert_eq!(civil_from_days(days_from_civil(2000, 2, 28) + 1), (2000, 2, 29));
+    }
+}
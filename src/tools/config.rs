@@ -52,26 +52,23 @@ pub fn get_config() -> Option<Config> {
     let config_dir_path = config_dir.join("r-port-doctor");
     let config_file_path = config_dir_path.join("config.toml");
     
-    if !config_file_path.exists() {
-        if fs::create_dir_all(&config_dir_path).is_ok() {
-            if let Ok(mut file) = fs::File::create(&config_file_path) {
-                let _ = file.write_all(get_default_config_content().as_bytes());
-            }
-        }
+    if !config_file_path.exists()
+        && fs::create_dir_all(&config_dir_path).is_ok()
+        && let Ok(mut file) = fs::File::create(&config_file_path) {
+        let _ = file.write_all(get_default_config_content().as_bytes());
     }
 
     let content = fs::read_to_string(config_file_path).ok()?;
-    let config = toml::from_str(&content).ok();
-    config
+    toml::from_str(&content).ok()
 }
 
 pub fn apply_config(config: Option<Config>, args: &mut Args) {
     if let Some(config) = config {
-        args.port = args.port.or(config.port);
-        args.remote_port = args.remote_port.or(config.remote_port);
+        args.port = args.port.clone().or(config.port.map(|p| p.to_string()));
+        args.remote_port = args.remote_port.clone().or(config.remote_port.map(|p| p.to_string()));
         args.mode = args.mode.clone().or(config.mode);
         args.process_name = args.process_name.clone().or(config.process_name);
-        args.pid = args.pid.or(config.pid);
+        args.pid = args.pid.clone().or(config.pid.map(|p| p.to_string()));
         args.state = args.state.clone().or(config.state);
         args.ip_version = args.ip_version.or(config.ip_version);
         args.local_address = args.local_address.clone().or(config.local_address);
@@ -81,7 +78,7 @@ pub fn apply_config(config: Option<Config>, args: &mut Args) {
 }
 
 // true = store as string | false = store as integer
-const CONFIG_KEYS: [(&'static str, bool); 10] =  [
+const CONFIG_KEYS: [(&str, bool); 10] =  [
     ("port", false),
     ("remote_port", false),
     ("mode", true),
@@ -102,12 +99,10 @@ fn verify_config_file() {
     let config_dir_path = config_dir.join("r-port-doctor");
     let config_file_path = config_dir_path.join("config.toml");
 
-    if !config_file_path.exists() {
-        if fs::create_dir_all(&config_dir_path).is_ok() {
-            if let Ok(mut file) = fs::File::create(&config_file_path) {
-                let _ = file.write_all(get_default_config_content().as_bytes());
-            }
-        }
+    if !config_file_path.exists()
+        && fs::create_dir_all(&config_dir_path).is_ok()
+        && let Ok(mut file) = fs::File::create(&config_file_path) {
+        let _ = file.write_all(get_default_config_content().as_bytes());
     }
 }
 
@@ -150,11 +145,11 @@ pub fn set_config_value(key: &str, value: Option<&String>) -> std::io::Result<()
     config_file_lines.iter_mut().for_each(|line| {
         if regex.is_match(line.trim()) {
             found_line = true;
-            if value.is_some() {
+            if let Some(value) = value {
                 if CONFIG_KEYS[target_key_idx].1 {
-                    *line = format!("{} = \"{}\"", key, value.unwrap());
+                    *line = format!("{} = \"{}\"", key, value);
                 } else {
-                    *line = format!("{} = {}", key, value.unwrap());
+                    *line = format!("{} = {}", key, value);
                 }
             } else {
                 *line = format!("# {} =", key);
@@ -163,11 +158,11 @@ pub fn set_config_value(key: &str, value: Option<&String>) -> std::io::Result<()
     });
 
     if !found_line {
-        if value.is_some() {
+        if let Some(value) = value {
             if CONFIG_KEYS[target_key_idx].1 {
-                config_file_lines.push(format!("{} = \"{}\"", key, value.unwrap()));
+                config_file_lines.push(format!("{} = \"{}\"", key, value));
             } else {
-                config_file_lines.push(format!("{} = {}", key, value.unwrap()));
+                config_file_lines.push(format!("{} = {}", key, value));
             }
         } else {
             config_file_lines.push(format!("# {} =", key));
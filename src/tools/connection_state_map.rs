@@ -14,4 +14,120 @@ pub fn map_tcp_state(state: u32) -> String {
         12 => "DELETE_TCB".to_string(),
         _ => "UNKNOWN".to_string(),
     }
-}
\ No newline at end of file
+}
+
+// Linux reports TCP states as the hex codes of include/net/tcp_states.h, mapped here to the names used by map_tcp_state
+pub fn map_proc_tcp_state(state: u8) -> String {
+    match state {
+        0x01 => "ESTABLISHED".to_string(),
+        0x02 => "SYN_SENT".to_string(),
+        0x03 => "SYN_RCVD".to_string(),
+        0x04 => "FIN_WAIT1".to_string(),
+        0x05 => "FIN_WAIT2".to_string(),
+        0x06 => "TIME_WAIT".to_string(),
+        0x07 => "CLOSED".to_string(),
+        0x08 => "CLOSE_WAIT".to_string(),
+        0x09 => "LAST_ACK".to_string(),
+        0x0A => "LISTEN".to_string(),
+        0x0B => "CLOSING".to_string(),
+        0x0C => "SYN_RCVD".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+const SO_ACCEPTCON: u32 = 0x10000;
+
+// Unix sockets report the socket_state enum of include/linux/net.h, listening sockets are flagged with __SO_ACCEPTCON instead
+pub fn map_unix_state(flags: u32, state: u8) -> String {
+    if flags & SO_ACCEPTCON != 0 {
+        return "LISTEN".to_string()
+    }
+    match state {
+        1 => "UNCONNECTED".to_string(),
+        2 => "CONNECTING".to_string(),
+        3 => "ESTABLISHED".to_string(),
+        4 => "DISCONNECTING".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+// sock_diag reports Unix socket states with the TCP state values
+pub fn map_unix_diag_state(state: u8) -> String {
+    match state {
+        0x01 => "ESTABLISHED".to_string(),
+        0x02 => "CONNECTING".to_string(),
+        0x07 => "UNCONNECTED".to_string(),
+        0x0A => "LISTEN".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+pub fn map_packet_type(socket_type: u16) -> String {
+    match socket_type {
+        2 => "DGRAM".to_string(),
+        3 => "RAW".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+pub fn map_unix_type(socket_type: u16) -> String {
+    match socket_type {
+        1 => "STREAM".to_string(),
+        2 => "DGRAM".to_string(),
+        5 => "SEQPACKET".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+// Raw sockets are bound to an IP protocol number from include/uapi/linux/in.h
+pub fn map_ip_protocol(protocol: u16) -> String {
+    match protocol {
+        1 => "ICMP".to_string(),
+        2 => "IGMP".to_string(),
+        6 => "TCP".to_string(),
+        17 => "UDP".to_string(),
+        47 => "GRE".to_string(),
+        58 => "ICMPv6".to_string(),
+        89 => "OSPF".to_string(),
+        112 => "VRRP".to_string(),
+        132 => "SCTP".to_string(),
+        255 => "RAW".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+// Packet sockets are bound to an ethertype from include/uapi/linux/if_ether.h, ETH_P_ALL receives every frame
+pub fn map_ethertype(ethertype: u16) -> String {
+    match ethertype {
+        0x0000 => "NONE".to_string(),
+        0x0003 => "ALL".to_string(),
+        0x0800 => "IPv4".to_string(),
+        0x0806 => "ARP".to_string(),
+        0x8035 => "RARP".to_string(),
+        0x8100 => "VLAN".to_string(),
+        0x86DD => "IPv6".to_string(),
+        0x888E => "EAPOL".to_string(),
+        0x88CC => "LLDP".to_string(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
+
+// State names printed by netstat and ss on Windows and Linux, mapped to the names used by map_tcp_state
+pub fn normalize_state_name(state: &str) -> Option<String> {
+    let state = state.to_uppercase().replace(['-', '_'], "");
+    let normalized = match state.as_str() {
+        "LISTEN" | "LISTENING" => "LISTEN",
+        "ESTAB" | "ESTABLISHED" => "ESTABLISHED",
+        "SYNSENT" => "SYN_SENT",
+        "SYNRECV" | "SYNRECEIVED" | "SYNRCVD" => "SYN_RCVD",
+        "FINWAIT1" => "FIN_WAIT1",
+        "FINWAIT2" => "FIN_WAIT2",
+        "CLOSEWAIT" => "CLOSE_WAIT",
+        "CLOSING" => "CLOSING",
+        "LASTACK" => "LAST_ACK",
+        "TIMEWAIT" => "TIME_WAIT",
+        "CLOSE" | "CLOSED" => "CLOSED",
+        "DELETETCB" => "DELETE_TCB",
+        _ => return None
+    };
+    Some(normalized.to_string())
+}
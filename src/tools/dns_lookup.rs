@@ -4,7 +4,7 @@ use std::collections::HashSet;
 use crate::tools::socket::Socket;
 use dashmap::DashMap;
 
-static DNS_CACHE: LazyLock<DashMap<String, String>> = LazyLock::new(|| DashMap::new());
+static DNS_CACHE: LazyLock<DashMap<String, String>> = LazyLock::new(DashMap::new);
 
 pub fn lookup_address(ip: &str) -> String {
     if let Some(cached) = DNS_CACHE.get(ip) {
@@ -21,28 +21,26 @@ pub fn lookup_address(ip: &str) -> String {
     hostname
 }
 
-pub fn resolve_socket_table_addresses(sockets: &mut Vec<Socket>) {
+pub fn resolve_socket_table_addresses(sockets: &mut [Socket]) {
     let threads = 4;
 
     let mut addresses_hash_set: HashSet<String> = HashSet::new();
 
     sockets.iter().for_each(|s| {
-        if s.protocol != "UDP" {
-            if let Some(addr) = &s.remote_addr {
-                if addr != "127.0.0.1" && addr != "0.0.0.0" {
-                    addresses_hash_set.insert(addr.clone());
-                }
-            }   
+        if s.protocol != "UDP"
+            && let Some(addr) = &s.remote_addr
+            && addr != "127.0.0.1" && addr != "0.0.0.0" {
+            addresses_hash_set.insert(addr.clone());
         }
     });
 
-    if addresses_hash_set.len() == 0 {
+    if addresses_hash_set.is_empty() {
         return
     }
 
     let addresses: Vec<String> = addresses_hash_set.into_iter().collect();
     let len = addresses.len();
-    let chunk_size = (len + threads - 1) / threads;
+    let chunk_size = len.div_ceil(threads);
 
     let mut handles = Vec::new();
 
@@ -64,12 +62,10 @@ pub fn resolve_socket_table_addresses(sockets: &mut Vec<Socket>) {
     handles.into_iter().for_each(|h| h.join().unwrap());
 
     sockets.iter_mut().for_each(|s| {
-        if s.protocol != "UDP" {
-            if let Some(addr) = &s.remote_addr {
-                if let Some(resolved_addr) = DNS_CACHE.get(addr) {
-                    s.remote_addr = Some(resolved_addr.clone());
-                }
-            }
+        if s.protocol != "UDP"
+            && let Some(addr) = &s.remote_addr
+            && let Some(resolved_addr) = DNS_CACHE.get(addr) {
+            s.remote_addr = Some(resolved_addr.clone());
         }
     });
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::{collections::HashMap, ffi::c_void, sync::LazyLock};
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Wdk::System::Threading::{NtQueryInformationProcess, ProcessCommandLineInformation},
+    Win32::Foundation::*,
+    Win32::Security::*,
+    Win32::System::Diagnostics::ToolHelp::*,
+    Win32::System::Threading::*,
+};
+use crate::tools::process_info::ProcessInfo;
+
+// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+// Parent PID and executable name of every running process, read from a single Toolhelp snapshot
+pub static PROCESS_TABLE: LazyLock<HashMap<u32, (u32, String)>> = LazyLock::new(|| {
+    let mut map: HashMap<u32, (u32, String)> = HashMap::new();
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else { return map };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        if Process32FirstW(snapshot, &mut entry).is_ok() {
+            loop {
+                let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+                map.insert(entry.th32ProcessID, (entry.th32ParentProcessID, String::from_utf16_lossy(&entry.szExeFile[..len])));
+                if Process32NextW(snapshot, &mut entry).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    map
+});
+
+fn get_start_time(handle: HANDLE) -> Option<u64> {
+    let (mut creation, mut exit, mut kernel, mut user) = (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+    unsafe { GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user) }.ok()?;
+    let creation_100ns = ((creation.dwHighDateTime as u64) << 32) | (creation.dwLowDateTime as u64);
+    (creation_100ns / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET)
+}
+
+// 'DOMAIN\user' of the process token, same as the 'User name' column of the Task Manager
+fn get_user(handle: HANDLE) -> Option<String> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(handle, TOKEN_QUERY, &mut token).ok()?;
+        let mut length = 0;
+        let _ = GetTokenInformation(token, TokenUser, None, 0, &mut length);
+        let mut buffer = vec![0u8; length as usize];
+        let result = GetTokenInformation(token, TokenUser, Some(buffer.as_mut_ptr() as *mut c_void), length, &mut length);
+        let _ = CloseHandle(token);
+        result.ok()?;
+        let token_user = &*(buffer.as_ptr() as *const TOKEN_USER);
+
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let (mut name_length, mut domain_length) = (name.len() as u32, domain.len() as u32);
+        let mut sid_use = SID_NAME_USE::default();
+        LookupAccountSidW(PCWSTR::null(), token_user.User.Sid, PWSTR(name.as_mut_ptr()), &mut name_length, PWSTR(domain.as_mut_ptr()), &mut domain_length, &mut sid_use).ok()?;
+        let name = String::from_utf16_lossy(&name[..name_length as usize]);
+        let domain = String::from_utf16_lossy(&domain[..domain_length as usize]);
+        Some(if domain.is_empty() { name } else { format!("{}\\{}", domain, name) })
+    }
+}
+
+// ProcessCommandLineInformation (Windows 8.1+) copies the command line out of the PEB of the process as a UNICODE_STRING
+// followed by its characters, it only needs the same limited query access as the other fields
+fn get_cmdline(handle: HANDLE) -> Option<String> {
+    unsafe {
+        let mut length = 0;
+        let _ = NtQueryInformationProcess(handle, ProcessCommandLineInformation, std::ptr::null_mut(), 0, &mut length);
+        if length == 0 {
+            return None
+        }
+        // u64 elements keep the UNICODE_STRING header aligned
+        let mut buffer = vec![0u64; (length as usize).div_ceil(8)];
+        if NtQueryInformationProcess(handle, ProcessCommandLineInformation, buffer.as_mut_ptr() as *mut c_void, length, &mut length).is_err() {
+            return None
+        }
+        let command_line = &*(buffer.as_ptr() as *const UNICODE_STRING);
+        if command_line.Buffer.is_null() || command_line.Length == 0 {
+            return None
+        }
+        let chars = std::slice::from_raw_parts(command_line.Buffer.0, command_line.Length as usize / 2);
+        Some(String::from_utf16_lossy(chars))
+    }
+}
+
+pub fn get_process_info(pid: u32) -> ProcessInfo {
+    let mut info = ProcessInfo {
+        ppid: PROCESS_TABLE.get(&pid).map(|(ppid, _)| *ppid),
+        ..Default::default()
+    };
+    if let Ok(handle) = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        info.start_time = get_start_time(handle);
+        info.user = get_user(handle);
+        info.cmdline = get_cmdline(handle);
+        unsafe { CloseHandle(handle).ok(); }
+    }
+    info
+}
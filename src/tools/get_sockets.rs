@@ -1,27 +1,139 @@
-use crate::tools::{args::Args, rpderror::RpdError, socket::Socket, tcp_table::*, udp_table::*};
+use crate::tools::{args::Args, input::read_socket_table, rpderror::RpdError, socket::Socket, socket_source::*};
 
-pub fn get_sockets(sockets: &mut Vec<Socket>, args: &Args) {
+pub fn get_socket_source(source: &Option<String>) -> Box<dyn SocketSource> {
+    let source = source.as_deref().map(|s| s.to_lowercase());
+    match source.as_deref() {
+        #[cfg(windows)]
+        Some("iphelper") | None => Box::new(IpHelperSource),
+        #[cfg(target_os = "linux")]
+        Some("netlink") | None => Box::new(NetlinkSource),
+        #[cfg(target_os = "linux")]
+        Some("procfs") => Box::new(ProcfsSource::default()),
+        #[cfg(test)]
+        Some("fixture") => Box::new(FixtureSource::default()),
+        Some(s) => RpdError::InvalidSourceErr(s.to_string()).handle()
+    }
+}
+
+pub fn get_sockets_from_source(source: &dyn SocketSource, sockets: &mut Vec<Socket>, args: &Args) {
+    // A replayed table is returned whole unless '--ip-version' or '--mode' are given, the live defaults only apply to the system
+    let replay = args.input.is_some();
     let (use_ipv4, use_ipv6) = match args.ip_version {
         Some(4) => (true, false),
         Some(6) => (false, true),
-        None => (true, false),
+        None => (true, replay),
         _ => RpdError::InvalidIpVersionErr().handle()
     };
 
-    let (use_tcp, use_udp) = match args.mode.as_deref().map(|s| s.to_lowercase()) {
-        Some(m) if m == "tcp" => (true, false),
-        Some(m) if m == "udp" => (false, true),
-        None => (true, true),
+    // Unix domain and packet sockets have no IP version
+    let (use_tcp, use_udp, use_raw, use_unix, use_packet) = match args.mode.as_deref().map(|s| s.to_lowercase()) {
+        Some(m) if m == "tcp" => (true, false, false, false, false),
+        Some(m) if m == "udp" => (false, true, false, false, false),
+        Some(m) if m == "raw" => (false, false, true, false, false),
+        Some(m) if m == "unix" => (false, false, false, true, false),
+        Some(m) if m == "packet" => (false, false, false, false, true),
+        None => (true, true, replay, replay, replay),
         Some(m) => RpdError::InvalidProtocolErr(m).handle()
-
     };
 
+    let first_collected = sockets.len();
+    if use_unix { sockets.extend(source.get_unix_sockets()); }
+    if use_packet { sockets.extend(source.get_packet_sockets()); }
     if use_ipv4 {
-        if use_tcp { sockets.extend(get_tcp_sockets()); }
-        if use_udp { sockets.extend(get_udp_sockets()); }
+        if use_tcp { sockets.extend(source.get_tcp_sockets(false)); }
+        if use_udp { sockets.extend(source.get_udp_sockets(false)); }
+        if use_raw { sockets.extend(source.get_raw_sockets(false)); }
     }
     if use_ipv6 {
-        if use_tcp { sockets.extend(get_tcp_sockets_ipv6()); }
-        if use_udp { sockets.extend(get_udp_sockets_ipv6()); }
+        if use_tcp { sockets.extend(source.get_tcp_sockets(true)); }
+        if use_udp { sockets.extend(source.get_udp_sockets(true)); }
+        if use_raw { sockets.extend(source.get_raw_sockets(true)); }
+    }
+    source.enrich_sockets(&mut sockets[first_collected..]);
+}
+
+// Our own namespace is read through the selected source, every other one through the procfs tables of a process inside it
+#[cfg(target_os = "linux")]
+fn get_sockets_from_all_netns(sockets: &mut Vec<Socket>, args: &Args) {
+    use crate::tools::netns::{get_own_netns, resolve_netns, NETNS_MAP};
+
+    let requested_netns = args.netns.map(|netns| resolve_netns(netns).unwrap_or_else(|| RpdError::InvalidNetnsErr(netns.to_string()).handle()));
+    let own_netns = get_own_netns();
+    for (&netns, &pid) in NETNS_MAP.iter() {
+        if matches!(requested_netns, Some(requested) if requested != netns) {
+            continue
+        }
+        let source: Box<dyn SocketSource> = if own_netns == Some(netns) {
+            get_socket_source(&args.source)
+        } else {
+            Box::new(ProcfsSource::for_pid(pid))
+        };
+        let mut netns_sockets: Vec<Socket> = Vec::new();
+        get_sockets_from_source(source.as_ref(), &mut netns_sockets, args);
+        netns_sockets.iter_mut().for_each(|socket| socket.netns = Some(netns));
+        sockets.extend(netns_sockets);
+    }
+}
+
+pub fn get_sockets(sockets: &mut Vec<Socket>, args: &Args) {
+    let source: Box<dyn SocketSource> = match &args.input {
+        Some(input) => Box::new(FixtureSource::new(read_socket_table(input, &args.input_format, &args.process_map))),
+        None if args.all_netns || args.netns.is_some() => {
+            #[cfg(target_os = "linux")]
+            return get_sockets_from_all_netns(sockets, args);
+            #[cfg(not(target_os = "linux"))]
+            RpdError::NetnsUnsupportedErr().handle()
+        }
+        None => get_socket_source(&args.source)
+    };
+    get_sockets_from_source(source.as_ref(), sockets, args);
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use super::*;
+
+    fn collect(flags: &[&str]) -> Vec<Socket> {
+        let args = Args::parse_from(["r-port-doctor"].iter().chain(flags));
+        let mut sockets = Vec::new();
+        get_sockets_from_source(&FixtureSource::default(), &mut sockets, &args);
+        sockets
+    }
+
+    fn replay(flags: &[&str]) -> Vec<Socket> {
+        collect(&[&["--input", "snapshot.json"], flags].concat())
+    }
+
+    fn is_ipv6(socket: &Socket) -> bool {
+        socket.local_addr.parse::<std::net::Ipv6Addr>().is_ok()
+    }
+
+    #[test]
+    fn live_listing_defaults_to_ipv4() {
+        let sockets = collect(&["--source", "fixture"]);
+        assert_eq!(sockets.len(), 13);
+        assert!(!sockets.iter().any(is_ipv6));
+    }
+
+    #[test]
+    fn replays_every_row_of_a_mixed_table() {
+        let sockets = replay(&[]);
+        assert_eq!(sockets.len(), FixtureSource::default().sockets.len());
+        assert!(sockets.iter().any(is_ipv6));
+    }
+
+    #[test]
+    fn ip_version_and_mode_narrow_a_replayed_table() {
+        assert!(!replay(&["-v", "6"]).iter().any(|s| s.local_addr.parse::<std::net::Ipv4Addr>().is_ok()));
+        let sockets = replay(&["-v", "6", "-m", "tcp"]);
+        assert_eq!(sockets.len(), 2);
+        assert!(sockets.iter().all(is_ipv6));
+        assert!(replay(&["-m", "udp"]).iter().all(|s| s.protocol == "UDP"));
+        assert_eq!(replay(&["-m", "udp"]).len(), 3);
+        assert_eq!(replay(&["-m", "unix"]).len(), 4);
+        assert_eq!(replay(&["-m", "raw"]).len(), 1);
+        assert_eq!(replay(&["-m", "packet"]).len(), 1);
+        assert_eq!(replay(&["-v", "4", "-m", "tcp"]).len(), 11);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,68 @@
+use std::{collections::HashMap, fs, io::Read};
+use serde_json::Value;
+use crate::tools::{lsof_input::parse_lsof, netstat_input::{parse_netstat, parse_ss}, pcap_input::parse_pcap, powershell_input::parse_powershell, rpderror::RpdError, socket::Socket, zeek_input::parse_zeek};
+
+// '-' reads from stdin so snapshots can be piped from another r-port-doctor run
+pub fn read_input_bytes(input: &str) -> Vec<u8> {
+    let content = if input == "-" {
+        let mut buffer = Vec::new();
+        std::io::stdin().read_to_end(&mut buffer).map(|_| buffer)
+    } else {
+        fs::read(input)
+    };
+    content.unwrap_or_else(|e| RpdError::ReadInputErr(input.to_string(), e.to_string()).handle())
+}
+
+pub fn read_input(input: &str) -> String {
+    String::from_utf8(read_input_bytes(input)).unwrap_or_else(|e| RpdError::ReadInputErr(input.to_string(), e.to_string()).handle())
+}
+
+// Flattens a JSON record into the same field map as the text formats. Booleans are written 'T' and 'F' like in Zeek TSV logs, nested values are left out
+pub fn json_to_row(object: &serde_json::Map<String, Value>) -> HashMap<String, String> {
+    object.iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => if *b { "T".to_string() } else { "F".to_string() },
+                _ => return None
+            };
+            Some((key.clone(), value))
+        })
+        .collect()
+}
+
+// Saved '--json' tables by default, or the output of other tools captured where r-port-doctor can't run.
+// 'process_map' is only used by formats that carry no process information
+pub fn read_socket_table(input: &str, format: &Option<String>, process_map: &Option<String>) -> Vec<Socket> {
+    let format = format.as_deref().map(|f| f.to_lowercase());
+    if format.as_deref() == Some("pcap") {
+        let owners = process_map.as_deref().map(|map| read_socket_table(map, &None, &None)).unwrap_or_default();
+        return parse_pcap(input, &read_input_bytes(input), &owners)
+    }
+    let content = read_input(input);
+    match format.as_deref() {
+        Some("json") | None => serde_json::from_str(&content).unwrap_or_else(|e| RpdError::ParseInputErr(input.to_string(), e.to_string()).handle()),
+        Some("netstat") => parse_netstat(&content),
+        Some("ss") => parse_ss(&content),
+        Some("lsof") => parse_lsof(&content),
+        Some("powershell") => parse_powershell(input, &content),
+        Some("zeek") => parse_zeek(input, &content),
+        Some(f) => RpdError::InvalidInputFormatErr(f.to_string()).handle()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::tools::socket::Socket;
+
+    // (PID, process name, protocol, local address, local port, remote address, remote port, state)
+    pub type Row<'a> = (u32, &'a str, &'a str, &'a str, u16, Option<&'a str>, Option<u16>, &'a str);
+
+    // Imported rows in a comparable form, shared by the tests of every input format
+    pub fn rows(sockets: &[Socket]) -> Vec<Row<'_>> {
+        sockets.iter()
+            .map(|s| (s.pid, s.process_name.as_str(), s.protocol, s.local_addr.as_str(), s.port, s.remote_addr.as_deref(), s.remote_port, s.state.as_str()))
+            .collect()
+    }
+}
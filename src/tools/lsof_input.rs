@@ -0,0 +1,137 @@
+use crate::tools::{connection_state_map::normalize_state_name, netstat_input::build_socket, socket::{map_protocol, Socket}};
+
+// Fields of the file set currently being read, a new 'f' line starts the next one
+#[derive(Default)]
+struct LsofFile {
+    ipv6: bool,
+    protocol: Option<&'static str>,
+    name: Option<String>,
+    state: Option<String>
+}
+
+impl LsofFile {
+    // 'n' holds '<local>' or '<local>-><remote>', e.g. '*:80' or '10.0.0.5:22->10.0.0.9:50122'
+    fn into_socket(self, pid: u32, command: &str) -> Option<Socket> {
+        let protocol = self.protocol.filter(|p| matches!(*p, "TCP" | "UDP"))?;
+        let name = self.name?;
+        let (local, remote) = name.split_once("->").unwrap_or((&name, "*:*"));
+        let state = self.state.unwrap_or_else(|| " ".to_string());
+        let mut socket = build_socket(protocol, local, remote, state, pid, command.to_string())?;
+        // Wildcards can only be told apart by the 't' field
+        if self.ipv6 {
+            if socket.local_addr == "0.0.0.0" { socket.local_addr = "::".to_string(); }
+            if let Some(remote_addr) = socket.remote_addr.as_mut().filter(|addr| *addr == "0.0.0.0") { *remote_addr = "::".to_string(); }
+        }
+        Some(socket)
+    }
+}
+
+// 'lsof -i -n -P -F pcnPtT' prints one field per line, the first character names the field:
+// p (PID) and c (command) describe a process, f (fd) starts each of its files, followed by t (IPv4/IPv6), P (protocol), n (addresses) and T (TCP info such as 'ST=LISTEN')
+pub fn parse_lsof(content: &str) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = Vec::new();
+    let mut pid: u32 = 0;
+    let mut command = String::from("unknown");
+    let mut file: Option<LsofFile> = None;
+
+    for line in content.lines() {
+        let mut chars = line.chars();
+        let Some(field) = chars.next() else { continue };
+        let value = chars.as_str();
+        match field {
+            'p' | 'f' => {
+                if let Some(socket) = file.take().and_then(|f| f.into_socket(pid, &command)) {
+                    sockets.push(socket);
+                }
+                if field == 'p' {
+                    pid = value.parse().unwrap_or(0);
+                    command = String::from("unknown");
+                } else {
+                    file = Some(LsofFile::default());
+                }
+            }
+            'c' => command = value.to_string(),
+            't' => if let Some(f) = file.as_mut() { f.ipv6 = value == "IPv6" },
+            'P' => if let Some(f) = file.as_mut() { f.protocol = map_protocol(value) },
+            'n' => if let Some(f) = file.as_mut() { f.name = Some(value.to_string()) },
+            'T' => {
+                if let (Some(f), Some(state)) = (file.as_mut(), value.strip_prefix("ST=")) {
+                    f.state = normalize_state_name(state);
+                }
+            }
+            _ => {}
+        }
+    }
+    if let Some(socket) = file.and_then(|f| f.into_socket(pid, &command)) {
+        sockets.push(socket);
+    }
+    sockets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::input::tests::rows;
+
+    // 'lsof -i -n -P -F pcnPtT' on a host with nginx, systemd-resolved and a Unix socket listed by a broader selection
+    const LSOF: &str = "p1200
+cnginx
+f6
+tIPv4
+PTCP
+n*:80
+TST=LISTEN
+TQR=0
+TQS=0
+f7
+tIPv6
+PTCP
+n*:443
+TST=LISTEN
+f12
+tIPv4
+PTCP
+n10.0.0.5:443->203.0.113.10:51234
+TST=ESTABLISHED
+p640
+csystemd-resolve
+f13
+tIPv4
+PUDP
+n127.0.0.53:53
+f14
+tIPv6
+PUDP
+n[::1]:323
+p900
+cdockerd
+f5
+tunix
+n/run/docker.sock
+p3100
+f9
+tIPv6
+PTCP
+n[fe80::1]:49900->[fe80::2]:8080
+TST=CLOSE_WAIT
+";
+
+    #[test]
+    fn parses_lsof_fields() {
+        assert_eq!(rows(&parse_lsof(LSOF)), vec![
+            (1200, "nginx", "TCP", "0.0.0.0", 80, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (1200, "nginx", "TCP", "::", 443, Some("::"), Some(0), "LISTEN"),
+            (1200, "nginx", "TCP", "10.0.0.5", 443, Some("203.0.113.10"), Some(51234), "ESTABLISHED"),
+            (640, "systemd-resolve", "UDP", "127.0.0.53", 53, None, None, " "),
+            (640, "systemd-resolve", "UDP", "::1", 323, None, None, " "),
+            (3100, "unknown", "TCP", "fe80::1", 49900, Some("fe80::2"), Some(8080), "CLOSE_WAIT")
+        ]);
+    }
+
+    #[test]
+    fn ignores_incomplete_files() {
+        assert!(parse_lsof("").is_empty());
+        assert!(parse_lsof("p1\ncsystemd\nf3\ntIPv4\nPTCP\n").is_empty());
+        assert_eq!(rows(&parse_lsof("p1\nf3\nPTCP\nn*:22\n")), vec![(1, "unknown", "TCP", "0.0.0.0", 22, Some("0.0.0.0"), Some(0), " ")]);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{collections::BTreeMap, fs, sync::LazyLock};
+
+// Every network namespace in use, keyed by its inode with the first process found inside it, whose /proc/<pid>/net shows the namespace tables
+pub static NETNS_MAP: LazyLock<BTreeMap<u64, u32>> = LazyLock::new(|| {
+    let mut map: BTreeMap<u64, u32> = BTreeMap::new();
+    let Ok(proc_dir) = fs::read_dir("/proc") else { return map };
+    for entry in proc_dir.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else { continue };
+        if let Some(netns) = get_netns(pid) {
+            map.entry(netns).or_insert(pid);
+        }
+    }
+    map
+});
+
+fn parse_netns_link(link: &str) -> Option<u64> {
+    link.strip_prefix("net:[")?.strip_suffix(']')?.parse().ok()
+}
+
+// /proc/<pid>/ns/net is a link to 'net:[<inode>]'
+pub fn get_netns(pid: u32) -> Option<u64> {
+    fs::read_link(format!("/proc/{}/ns/net", pid))
+        .ok()
+        .and_then(|link| parse_netns_link(&link.to_string_lossy()))
+}
+
+pub fn get_own_netns() -> Option<u64> {
+    fs::read_link("/proc/self/ns/net")
+        .ok()
+        .and_then(|link| parse_netns_link(&link.to_string_lossy()))
+}
+
+// Sockets opened by a thread live in the namespace of that thread, which differs from ours after a setns(2)
+pub fn get_thread_netns() -> Option<u64> {
+    fs::read_link("/proc/thread-self/ns/net")
+        .ok()
+        .and_then(|link| parse_netns_link(&link.to_string_lossy()))
+}
+
+// '--netns' takes either a namespace inode or the PID of a process inside the namespace
+pub fn resolve_netns(value: u64) -> Option<u64> {
+    if NETNS_MAP.contains_key(&value) {
+        return Some(value)
+    }
+    u32::try_from(value).ok().and_then(get_netns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_netns_links() {
+        assert_eq!(parse_netns_link("net:[4026531840]"), Some(4026531840));
+        assert_eq!(parse_netns_link("socket:[4026531840]"), None);
+        assert_eq!(parse_netns_link("net:[]"), None);
+    }
+
+    #[test]
+    fn reads_own_namespace() {
+        let own_netns = get_own_netns();
+        assert!(own_netns.is_some());
+        assert_eq!(get_thread_netns(), own_netns);
+        assert_eq!(get_netns(std::process::id()), own_netns);
+    }
+
+    #[test]
+    fn resolves_namespace_from_inode_or_pid() {
+        let own_netns = get_own_netns().unwrap();
+        assert_eq!(resolve_netns(own_netns), Some(own_netns));
+        assert_eq!(resolve_netns(std::process::id() as u64), Some(own_netns));
+        assert_eq!(resolve_netns(u64::MAX), None);
+    }
+}
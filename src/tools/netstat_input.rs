@@ -0,0 +1,257 @@
+use std::{net::IpAddr, sync::LazyLock};
+use regex::Regex;
+use crate::tools::{connection_state_map::normalize_state_name, socket::{map_protocol, Socket}};
+
+// First process of the 'users:(("name",pid=1,fd=3),...)' column of 'ss -p'
+static SS_USERS_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\("([^"]*)",pid=(\d+)"#).unwrap());
+
+// '0.0.0.0:135', '[::]:135', '[fe80::1%4]:1900', '127.0.0.53%lo:53', ':::22' or '*:*', a '*' port is returned as None
+pub fn parse_endpoint(endpoint: &str) -> Option<(String, Option<u16>)> {
+    let (host, port) = endpoint.rsplit_once(':')?;
+    let port = if port == "*" { None } else { Some(port.parse::<u16>().ok()?) };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next().unwrap_or(host);
+    Some((host.to_string(), port))
+}
+
+// '*' stands for every address of the same family as the other end of the row
+fn resolve_wildcard(host: String, other_host: &str) -> String {
+    match (host.as_str(), other_host.parse::<IpAddr>()) {
+        ("*", Ok(IpAddr::V6(_))) => "::".to_string(),
+        ("*", _) => "0.0.0.0".to_string(),
+        _ => host
+    }
+}
+
+pub fn build_socket(protocol: &'static str, local: &str, remote: &str, state: String, pid: u32, process_name: String) -> Option<Socket> {
+    let (local_addr, port) = parse_endpoint(local)?;
+    let (remote_addr, remote_port) = parse_endpoint(remote)?;
+    let local_addr = resolve_wildcard(local_addr, &remote_addr);
+    let remote_addr = resolve_wildcard(remote_addr, &local_addr);
+    let is_tcp = protocol == "TCP";
+    Some(Socket {
+        process_name,
+        pid,
+        port: port.unwrap_or(0),
+        protocol,
+        local_addr,
+        // UDP sockets have no remote end, same as the live collectors
+        remote_addr: if is_tcp { Some(remote_addr) } else { None },
+        remote_port: if is_tcp { Some(remote_port.unwrap_or(0)) } else { None },
+        state: if is_tcp { state } else { " ".to_string() },
+        executable_path: None,
+        uptime: 0,
+        ..Default::default()
+    })
+}
+
+pub fn default_process_name(pid: u32) -> String {
+    match pid {
+        4 => "SYSTEM".to_string(),
+        _ => "unknown".to_string()
+    }
+}
+
+// 'Proto Local-Address Foreign-Address State PID' on Windows (-ano), with 'Recv-Q Send-Q' after the protocol and 'PID/Program name' on Linux (-tanup).
+// 'netstat -b' prints the executable name in brackets on the line following each row
+pub fn parse_netstat(content: &str) -> Vec<Socket> {
+    let mut sockets: Vec<Socket> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if let Some(last) = sockets.last_mut() {
+                last.process_name = name.to_string();
+            }
+            continue
+        }
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let Some(protocol) = columns.first().and_then(|p| map_protocol(p.trim_end_matches(['6', '4']))) else { continue };
+        if !matches!(protocol, "TCP" | "UDP") {
+            continue
+        }
+        let mut rest = &columns[1..];
+        let mut queues: Option<(u32, u32)> = None;
+        if let (Some(Ok(recv_q)), Some(Ok(send_q))) = (rest.first().map(|q| q.parse()), rest.get(1).map(|q| q.parse())) {
+            queues = Some((recv_q, send_q));
+            rest = &rest[2..];
+        }
+        let (Some(local), Some(remote)) = (rest.first(), rest.get(1)) else { continue };
+        rest = &rest[2..];
+        let state = match rest.first().and_then(|s| normalize_state_name(s)) {
+            Some(state) => {
+                rest = &rest[1..];
+                state
+            }
+            None => " ".to_string()
+        };
+        // '1234' on Windows, '1234/sshd: /usr/sbin' or '-' on Linux
+        let owner = rest.join(" ");
+        let (pid, process_name) = match owner.split_once('/') {
+            Some((pid, name)) => (pid.parse::<u32>().unwrap_or(0), name.split(':').next().unwrap_or(name).to_string()),
+            None => (owner.parse::<u32>().unwrap_or(0), String::new())
+        };
+        let process_name = if process_name.is_empty() { default_process_name(pid) } else { process_name };
+        if let Some(mut socket) = build_socket(protocol, local, remote, state, pid, process_name) {
+            socket.recv_q = queues.map(|(recv_q, _)| recv_q);
+            socket.send_q = queues.map(|(_, send_q)| send_q);
+            sockets.push(socket);
+        }
+    }
+    sockets
+}
+
+// 'Netid State Recv-Q Send-Q Local-Address:Port Peer-Address:Port Process', the Netid column is missing when a single protocol is listed
+pub fn parse_ss(content: &str) -> Vec<Socket> {
+    content.lines()
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let (protocol, rest) = match columns.first().and_then(|netid| map_protocol(netid)) {
+                Some(protocol) => (protocol, &columns[1..]),
+                None => ("TCP", &columns[..])
+            };
+            if !matches!(protocol, "TCP" | "UDP") || rest.len() < 5 {
+                return None
+            }
+            let (state, local, remote) = (rest[0], rest[3], rest[4]);
+            let state = match normalize_state_name(state) {
+                Some(state) => state,
+                None if state == "UNCONN" => " ".to_string(),
+                None => return None
+            };
+            // Rows without a Netid column can only be told apart by UDP's UNCONN state
+            let protocol = if columns.len() == rest.len() && state == " " { "UDP" } else { protocol };
+            let (pid, process_name) = SS_USERS_REGEX.captures(line)
+                .and_then(|caps| Some((caps.get(2)?.as_str().parse::<u32>().ok()?, caps.get(1)?.as_str().to_string())))
+                .unwrap_or_else(|| (0, "unknown".to_string()));
+            let mut socket = build_socket(protocol, local, remote, state, pid, process_name)?;
+            socket.recv_q = rest[1].parse().ok();
+            socket.send_q = rest[2].parse().ok();
+            Some(socket)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::input::tests::rows;
+
+    fn queues(sockets: &[Socket]) -> Vec<(Option<u32>, Option<u32>)> {
+        sockets.iter().map(|s| (s.recv_q, s.send_q)).collect()
+    }
+
+    const WINDOWS_NETSTAT: &str = "
+Active Connections
+
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1032
+  TCP    0.0.0.0:445            0.0.0.0:0              LISTENING       4
+  TCP    10.0.0.5:49822         93.184.216.34:443      ESTABLISHED     3100
+  TCP    [::]:445               [::]:0                 LISTENING       4
+  TCP    [fe80::1%4]:49900      [fe80::2%4]:8080       TIME_WAIT       0
+  UDP    0.0.0.0:5353           *:*                                    720
+  UDP    [::]:5355              *:*                                    1400
+";
+
+    // 'netstat -anob', the service and executable are printed below each row
+    const WINDOWS_NETSTAT_B: &str = "
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1032
+  RpcSs
+ [svchost.exe]
+  TCP    10.0.0.5:49822         93.184.216.34:443      ESTABLISHED     3100
+ [chrome.exe]
+";
+
+    const LINUX_NETSTAT: &str = "
+Active Internet connections (servers and established)
+Proto Recv-Q Send-Q Local Address           Foreign Address         State       PID/Program name
+tcp        0      0 127.0.0.53:53           0.0.0.0:*               LISTEN      640/systemd-resolve
+tcp        0     36 10.0.0.5:22             203.0.113.10:51234      ESTABLISHED 1200/sshd: alice [p
+tcp6       0      0 :::80                   :::*                    LISTEN      -
+udp        0      0 0.0.0.0:68              0.0.0.0:*                           710/dhclient
+udp6     768      0 ::1:323                 :::*                                -
+";
+
+    const SS: &str = r#"
+Netid State   Recv-Q Send-Q   Local Address:Port    Peer Address:Port Process
+udp   UNCONN  0      0        127.0.0.53%lo:53          0.0.0.0:*     users:(("systemd-resolve",pid=640,fd=13))
+tcp   LISTEN  0      4096           0.0.0.0:22          0.0.0.0:*     users:(("sshd",pid=1200,fd=3))
+tcp   ESTAB   0      36            10.0.0.5:22     203.0.113.10:51234 users:(("sshd",pid=2500,fd=4),("sshd",pid=2600,fd=4))
+tcp   LISTEN  0      511               [::]:80             [::]:*
+u_str ESTAB   0      0                    * 31337              * 31338
+"#;
+
+    // A single protocol listed, without the Netid column
+    const SS_NO_NETID: &str = "
+State  Recv-Q Send-Q Local Address:Port  Peer Address:Port Process
+LISTEN 0      128          0.0.0.0:22         0.0.0.0:*
+ESTAB  0      0           10.0.0.5:22    203.0.113.10:51234
+UNCONN 0      0            0.0.0.0:68         0.0.0.0:*
+";
+
+    #[test]
+    fn parses_windows_netstat() {
+        let sockets = parse_netstat(WINDOWS_NETSTAT);
+        assert_eq!(rows(&sockets), vec![
+            (1032, "unknown", "TCP", "0.0.0.0", 135, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (4, "SYSTEM", "TCP", "0.0.0.0", 445, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (3100, "unknown", "TCP", "10.0.0.5", 49822, Some("93.184.216.34"), Some(443), "ESTABLISHED"),
+            (4, "SYSTEM", "TCP", "::", 445, Some("::"), Some(0), "LISTEN"),
+            (0, "unknown", "TCP", "fe80::1", 49900, Some("fe80::2"), Some(8080), "TIME_WAIT"),
+            (720, "unknown", "UDP", "0.0.0.0", 5353, None, None, " "),
+            (1400, "unknown", "UDP", "::", 5355, None, None, " ")
+        ]);
+        assert!(queues(&sockets).iter().all(|q| *q == (None, None)));
+    }
+
+    #[test]
+    fn names_processes_from_netstat_b() {
+        assert_eq!(rows(&parse_netstat(WINDOWS_NETSTAT_B)), vec![
+            (1032, "svchost.exe", "TCP", "0.0.0.0", 135, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (3100, "chrome.exe", "TCP", "10.0.0.5", 49822, Some("93.184.216.34"), Some(443), "ESTABLISHED")
+        ]);
+    }
+
+    #[test]
+    fn parses_linux_netstat() {
+        let sockets = parse_netstat(LINUX_NETSTAT);
+        assert_eq!(rows(&sockets), vec![
+            (640, "systemd-resolve", "TCP", "127.0.0.53", 53, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (1200, "sshd", "TCP", "10.0.0.5", 22, Some("203.0.113.10"), Some(51234), "ESTABLISHED"),
+            (0, "unknown", "TCP", "::", 80, Some("::"), Some(0), "LISTEN"),
+            (710, "dhclient", "UDP", "0.0.0.0", 68, None, None, " "),
+            (0, "unknown", "UDP", "::1", 323, None, None, " ")
+        ]);
+        assert_eq!(queues(&sockets), vec![(Some(0), Some(0)), (Some(0), Some(36)), (Some(0), Some(0)), (Some(0), Some(0)), (Some(768), Some(0))]);
+    }
+
+    #[test]
+    fn parses_ss() {
+        let sockets = parse_ss(SS);
+        assert_eq!(rows(&sockets), vec![
+            (640, "systemd-resolve", "UDP", "127.0.0.53", 53, None, None, " "),
+            (1200, "sshd", "TCP", "0.0.0.0", 22, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (2500, "sshd", "TCP", "10.0.0.5", 22, Some("203.0.113.10"), Some(51234), "ESTABLISHED"),
+            (0, "unknown", "TCP", "::", 80, Some("::"), Some(0), "LISTEN")
+        ]);
+        assert_eq!(queues(&sockets), vec![(Some(0), Some(0)), (Some(0), Some(4096)), (Some(0), Some(36)), (Some(0), Some(511))]);
+    }
+
+    #[test]
+    fn parses_ss_without_netid() {
+        assert_eq!(rows(&parse_ss(SS_NO_NETID)), vec![
+            (0, "unknown", "TCP", "0.0.0.0", 22, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (0, "unknown", "TCP", "10.0.0.5", 22, Some("203.0.113.10"), Some(51234), "ESTABLISHED"),
+            (0, "unknown", "UDP", "0.0.0.0", 68, None, None, " ")
+        ]);
+    }
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(parse_endpoint("[fe80::1%4]:1900"), Some(("fe80::1".to_string(), Some(1900))));
+        assert_eq!(parse_endpoint("127.0.0.53%lo:53"), Some(("127.0.0.53".to_string(), Some(53))));
+        assert_eq!(parse_endpoint(":::22"), Some(("::".to_string(), Some(22))));
+        assert_eq!(parse_endpoint("*:*"), Some(("*".to_string(), None)));
+        assert_eq!(parse_endpoint("10.0.0.5:http"), None);
+    }
+}
@@ -35,8 +35,5 @@ pub static NT_TO_DOS_MAP: LazyLock<HashMap<String, String>> = LazyLock::new(|| {
 
 pub fn to_dos_path(nt_path: &str) -> Option<String> {
     let nt_drive = nt_path.split("\\").take(3).collect::<Vec<_>>().join("\\");
-    match NT_TO_DOS_MAP.get(&nt_drive).cloned() {
-        Some(dos_path) => Some(nt_path.replacen(nt_drive.as_str(), &dos_path, 1)),
-        None => None
-    }
+    NT_TO_DOS_MAP.get(&nt_drive).map(|dos_path| nt_path.replacen(nt_drive.as_str(), dos_path, 1))
 }
\ No newline at end of file
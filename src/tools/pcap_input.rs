@@ -0,0 +1,532 @@
+use std::{collections::HashMap, net::{IpAddr, Ipv4Addr, Ipv6Addr}};
+use crate::tools::{rpderror::RpdError, socket::{Protocol, Socket}};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b23c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+const LINKTYPE_NULL: u16 = 0;
+const LINKTYPE_ETHERNET: u16 = 1;
+const LINKTYPE_RAW: u16 = 101;
+const LINKTYPE_LINUX_SLL: u16 = 113;
+const LINKTYPE_LINUX_SLL2: u16 = 276;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+const TCP_ACK: u8 = 0x10;
+
+struct Packet<'a> {
+    // Seconds since the epoch
+    timestamp: u64,
+    link_type: u16,
+    data: &'a [u8]
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    big_endian: bool
+}
+
+impl Reader<'_> {
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.bytes.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.bytes.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+}
+
+fn read_be_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+// 24 byte global header followed by 16 byte record headers (ts_sec, ts_frac, incl_len, orig_len)
+fn read_pcap_packets(bytes: &[u8]) -> Option<Vec<Packet<'_>>> {
+    let magic = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+    let big_endian = match magic {
+        PCAP_MAGIC_MICROS | PCAP_MAGIC_NANOS => false,
+        m if m.swap_bytes() == PCAP_MAGIC_MICROS || m.swap_bytes() == PCAP_MAGIC_NANOS => true,
+        _ => return None
+    };
+    let reader = Reader { bytes, big_endian };
+    let link_type = reader.u32(20)? as u16;
+    let mut packets: Vec<Packet> = Vec::new();
+    let mut offset = 24;
+    while let (Some(timestamp), Some(length)) = (reader.u32(offset), reader.u32(offset + 8)) {
+        let start = offset + 16;
+        let Some(data) = bytes.get(start..start + length as usize) else { break };
+        packets.push(Packet { timestamp: timestamp as u64, link_type, data });
+        offset = start + length as usize;
+    }
+    Some(packets)
+}
+
+// Timestamps of enhanced packet blocks are counted in units of the 'if_tsresol' option of their interface, microseconds by default
+fn read_interface_resolution(reader: &Reader, block_start: usize, block_end: usize) -> u64 {
+    let mut offset = block_start + 16;
+    while let (Some(code), Some(length)) = (reader.u16(offset), reader.u16(offset + 2)) {
+        if code == 0 || offset + 4 > block_end {
+            break
+        }
+        if code == 9 && let Some(&resolution) = reader.bytes.get(offset + 4) {
+            return match resolution & 0x80 {
+                0 => 10u64.saturating_pow(resolution as u32),
+                _ => 2u64.saturating_pow((resolution & 0x7f) as u32)
+            }
+        }
+        offset += 4 + (length as usize).div_ceil(4) * 4;
+    }
+    1_000_000
+}
+
+// Sequence of (type, total length, body, total length) blocks, each section header sets the byte order of the blocks that follow it
+fn read_pcapng_packets(bytes: &[u8]) -> Option<Vec<Packet<'_>>> {
+    if u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?) != PCAPNG_SECTION_HEADER {
+        return None
+    }
+    let mut reader = Reader { bytes, big_endian: false };
+    let mut interfaces: Vec<(u16, u64)> = Vec::new();
+    let mut packets: Vec<Packet> = Vec::new();
+    let mut offset = 0;
+    while offset + 12 <= bytes.len() {
+        if u32::from_le_bytes(bytes[offset..offset + 4].try_into().ok()?) == PCAPNG_SECTION_HEADER {
+            reader.big_endian = u32::from_le_bytes(bytes.get(offset + 8..offset + 12)?.try_into().ok()?) != PCAPNG_BYTE_ORDER_MAGIC;
+            interfaces.clear();
+        }
+        let block_type = reader.u32(offset)?;
+        let block_length = reader.u32(offset + 4)? as usize;
+        if block_length < 12 || offset + block_length > bytes.len() {
+            break
+        }
+        let block_end = offset + block_length - 4;
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => interfaces.push((reader.u16(offset + 8)?, read_interface_resolution(&reader, offset, block_end))),
+            PCAPNG_ENHANCED_PACKET => {
+                let (link_type, resolution) = *interfaces.get(reader.u32(offset + 8)? as usize)?;
+                let timestamp = ((reader.u32(offset + 12)? as u64) << 32) | reader.u32(offset + 16)? as u64;
+                let length = reader.u32(offset + 20)? as usize;
+                if let Some(data) = bytes.get(offset + 28..(offset + 28 + length).min(block_end)) {
+                    packets.push(Packet { timestamp: timestamp / resolution.max(1), link_type, data });
+                }
+            }
+            // Simple packet blocks carry no timestamp, they are given the one of the previous packet
+            PCAPNG_SIMPLE_PACKET => {
+                let (link_type, _) = *interfaces.first()?;
+                let timestamp = packets.last().map_or(0, |p| p.timestamp);
+                if let Some(data) = bytes.get(offset + 12..block_end) {
+                    packets.push(Packet { timestamp, link_type, data });
+                }
+            }
+            _ => {}
+        }
+        offset += block_length;
+    }
+    Some(packets)
+}
+
+// Returns the ethertype (0x0800 or 0x86DD) and the start of the IP header
+fn strip_link_layer(link_type: u16, data: &[u8]) -> Option<(u16, usize)> {
+    match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = read_be_u16(data, offset)?;
+            // 802.1Q and 802.1ad tags
+            while matches!(ethertype, 0x8100 | 0x88a8) {
+                offset += 4;
+                ethertype = read_be_u16(data, offset)?;
+            }
+            Some((ethertype, offset + 2))
+        }
+        LINKTYPE_LINUX_SLL => Some((read_be_u16(data, 14)?, 16)),
+        LINKTYPE_LINUX_SLL2 => Some((read_be_u16(data, 0)?, 20)),
+        // Address family in host byte order of the capturing machine, 2 for IPv4 and 24, 28 or 30 for IPv6 depending on the BSD
+        LINKTYPE_NULL => {
+            let family = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+            let family = if family > 0xffff { family.swap_bytes() } else { family };
+            Some((if family == 2 { 0x0800 } else { 0x86dd }, 4))
+        }
+        LINKTYPE_RAW | 12 | 14 => Some((if data.first()? >> 4 == 6 { 0x86dd } else { 0x0800 }, 0)),
+        _ => None
+    }
+}
+
+type Endpoint = (IpAddr, u16);
+
+struct Segment {
+    protocol: &'static str,
+    src: Endpoint,
+    dst: Endpoint,
+    flags: u8
+}
+
+fn parse_segment(link_type: u16, data: &[u8]) -> Option<Segment> {
+    let (ethertype, ip_start) = strip_link_layer(link_type, data)?;
+    let ip = data.get(ip_start..)?;
+    let (ip_protocol, src, dst, payload_start) = match ethertype {
+        0x0800 => {
+            // Only the first fragment carries the transport header
+            if read_be_u16(ip, 6)? & 0x1fff != 0 {
+                return None
+            }
+            let src: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            (*ip.get(9)?, IpAddr::V4(Ipv4Addr::from(src)), IpAddr::V4(Ipv4Addr::from(dst)), ((ip.first()? & 0x0f) as usize) * 4)
+        }
+        0x86dd => {
+            let src: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            (*ip.get(6)?, IpAddr::V6(Ipv6Addr::from(src)), IpAddr::V6(Ipv6Addr::from(dst)), 40)
+        }
+        _ => return None
+    };
+    let transport = ip.get(payload_start..)?;
+    let (src_port, dst_port) = (read_be_u16(transport, 0)?, read_be_u16(transport, 2)?);
+    let (protocol, flags) = match ip_protocol {
+        6 => ("TCP", *transport.get(13)?),
+        17 => ("UDP", 0),
+        _ => return None
+    };
+    Some(Segment { protocol, src: (src, src_port), dst: (dst, dst_port), flags })
+}
+
+// Endpoints are stored as (local, remote), local starts as the side that answered the handshake so the port column holds the service port
+struct Flow {
+    protocol: &'static str,
+    local: Endpoint,
+    remote: Endpoint,
+    local_is_responder: bool,
+    first_seen: u64,
+    syn: bool,
+    syn_ack: bool,
+    established: bool,
+    rst: bool,
+    fin_local: bool,
+    fin_remote: bool,
+    // Side that sent the first FIN, true for the local one
+    local_closed_first: Option<bool>,
+    pid: u32,
+    process_name: String,
+    executable_path: Option<String>
+}
+
+impl Flow {
+    fn new(segment: &Segment, timestamp: u64) -> Self {
+        // Without the handshake the lower port is assumed to be the service one, UDP flows are answered by the receiver of their first datagram
+        let src_is_local = match segment.protocol {
+            "TCP" if segment.flags & (TCP_SYN | TCP_ACK) == TCP_SYN => false,
+            "TCP" if segment.flags & (TCP_SYN | TCP_ACK) == TCP_SYN | TCP_ACK => true,
+            "UDP" => false,
+            _ => segment.src.1 < segment.dst.1
+        };
+        let (local, remote) = if src_is_local { (segment.src, segment.dst) } else { (segment.dst, segment.src) };
+        Flow {
+            protocol: segment.protocol,
+            local,
+            remote,
+            local_is_responder: true,
+            first_seen: timestamp,
+            syn: false,
+            syn_ack: false,
+            established: false,
+            rst: false,
+            fin_local: false,
+            fin_remote: false,
+            local_closed_first: None,
+            pid: 0,
+            process_name: "unknown".to_string(),
+            executable_path: None
+        }
+    }
+
+    fn update(&mut self, segment: &Segment) {
+        let from_local = segment.src == self.local;
+        let flags = segment.flags;
+        if flags & TCP_SYN != 0 {
+            if flags & TCP_ACK != 0 { self.syn_ack = true } else { self.syn = true }
+        } else if flags & TCP_ACK != 0 {
+            // Any acknowledged segment outside the handshake means the connection got established, even if the handshake wasn't captured
+            self.established = true;
+        }
+        if flags & TCP_RST != 0 {
+            self.rst = true;
+        }
+        if flags & TCP_FIN != 0 {
+            self.local_closed_first.get_or_insert(from_local);
+            if from_local { self.fin_local = true } else { self.fin_remote = true }
+        }
+    }
+
+    fn flip(&mut self) {
+        std::mem::swap(&mut self.local, &mut self.remote);
+        std::mem::swap(&mut self.fin_local, &mut self.fin_remote);
+        self.local_is_responder = !self.local_is_responder;
+        self.local_closed_first = self.local_closed_first.map(|local| !local);
+    }
+
+    // State of the local side after the last seen flags
+    fn state(&self) -> String {
+        let state = match (self.rst, self.fin_local, self.fin_remote, self.local_closed_first) {
+            (true, _, _, _) => "CLOSED",
+            (_, true, true, Some(true)) => "TIME_WAIT",
+            (_, true, true, _) => "CLOSED",
+            (_, true, false, _) => "FIN_WAIT1",
+            (_, false, true, _) => "CLOSE_WAIT",
+            _ if self.established => "ESTABLISHED",
+            _ if (self.syn || self.syn_ack) && self.local_is_responder => "SYN_RCVD",
+            _ if self.syn || self.syn_ack => "SYN_SENT",
+            _ => "ESTABLISHED"
+        };
+        state.to_string()
+    }
+
+    fn into_socket(self, capture_end: u64) -> Socket {
+        let state = if self.protocol == "TCP" { self.state() } else { " ".to_string() };
+        Socket {
+            process_name: self.process_name,
+            pid: self.pid,
+            port: self.local.1,
+            protocol: self.protocol,
+            local_addr: self.local.0.to_string(),
+            remote_addr: Some(self.remote.0.to_string()),
+            remote_port: Some(self.remote.1),
+            state,
+            executable_path: self.executable_path,
+            // Age of the flow when the capture ended
+            uptime: capture_end.saturating_sub(self.first_seen),
+            ..Default::default()
+        }
+    }
+}
+
+fn endpoint_matches(owner: &Socket, protocol: &str, endpoint: &Endpoint) -> bool {
+    let unspecified = owner.local_addr.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+    owner.protocol == protocol && owner.port == endpoint.1 && (unspecified || owner.local_addr == endpoint.0.to_string())
+}
+
+// The mapping table is matched on the local endpoint of each flow, flows whose remote endpoint matches instead are flipped
+// so the local side is always the one of the known process
+fn apply_process_map(flow: &mut Flow, owners: &[Socket]) {
+    let owner = match owners.iter().find(|o| endpoint_matches(o, flow.protocol, &flow.local)) {
+        Some(owner) => owner,
+        None => match owners.iter().find(|o| endpoint_matches(o, flow.protocol, &flow.remote)) {
+            Some(owner) => {
+                flow.flip();
+                owner
+            }
+            None => return
+        }
+    };
+    flow.pid = owner.pid;
+    flow.process_name = owner.process_name.clone();
+    flow.executable_path = owner.executable_path.clone();
+}
+
+// 'owners' is a socket table saved with '--json' on one of the captured hosts, without it every process is unknown
+pub fn parse_pcap(input: &str, bytes: &[u8], owners: &[Socket]) -> Vec<Socket> {
+    let Some(packets) = read_pcap_packets(bytes).or_else(|| read_pcapng_packets(bytes)) else {
+        RpdError::ParseInputErr(input.to_string(), "not a pcap or pcapng capture".to_string()).handle()
+    };
+    let capture_end = packets.iter().map(|p| p.timestamp).max().unwrap_or(0);
+
+    // Flows are keyed by their endpoints in a fixed order so both directions land on the same entry
+    let mut flow_index: HashMap<(Protocol, Endpoint, Endpoint), usize> = HashMap::new();
+    let mut flows: Vec<Flow> = Vec::new();
+    for packet in &packets {
+        let Some(segment) = parse_segment(packet.link_type, packet.data) else { continue };
+        let key = (segment.protocol, segment.src.min(segment.dst), segment.src.max(segment.dst));
+        let idx = *flow_index.entry(key).or_insert_with(|| {
+            flows.push(Flow::new(&segment, packet.timestamp));
+            flows.len() - 1
+        });
+        flows[idx].update(&segment);
+    }
+    flows.into_iter()
+        .map(|mut flow| {
+            apply_process_map(&mut flow, owners);
+            flow.into_socket(capture_end)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::input::tests::rows;
+
+    const CLIENT: &str = "192.0.2.10";
+    const SERVER: &str = "10.0.0.5";
+
+    fn tcp_packet(src: &str, src_port: u16, dst: &str, dst_port: u16, flags: u8) -> Vec<u8> {
+        ip_packet(src, dst, 6, [&src_port.to_be_bytes()[..], &dst_port.to_be_bytes(), &[0; 9], &[flags], &[0; 6]].concat())
+    }
+
+    fn udp_packet(src: &str, src_port: u16, dst: &str, dst_port: u16) -> Vec<u8> {
+        ip_packet(src, dst, 17, [&src_port.to_be_bytes()[..], &dst_port.to_be_bytes(), &[0, 8, 0, 0]].concat())
+    }
+
+    fn ip_packet(src: &str, dst: &str, protocol: u8, transport: Vec<u8>) -> Vec<u8> {
+        let header = match (src.parse::<IpAddr>().unwrap(), dst.parse::<IpAddr>().unwrap()) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => [&[0x45, 0, 0, 0, 0, 0, 0x40, 0, 64, protocol, 0, 0][..], &src.octets(), &dst.octets()].concat(),
+            (IpAddr::V6(src), IpAddr::V6(dst)) => [&[0x60, 0, 0, 0, 0, 0, protocol, 64][..], &src.octets(), &dst.octets()].concat(),
+            _ => unreachable!()
+        };
+        [header, transport].concat()
+    }
+
+    fn ethertype(ip: &[u8]) -> [u8; 2] {
+        if ip[0] >> 4 == 6 { [0x86, 0xdd] } else { [0x08, 0x00] }
+    }
+
+    // Destination and source MACs, with an 802.1Q tag on IPv6 frames
+    fn ethernet_frame(ip: &[u8]) -> Vec<u8> {
+        let vlan: &[u8] = if ip[0] >> 4 == 6 { &[0x81, 0x00, 0x00, 0x0a] } else { &[] };
+        [&[0xaa; 12][..], vlan, &ethertype(ip), ip].concat()
+    }
+
+    fn linux_sll_frame(ip: &[u8]) -> Vec<u8> {
+        [&[0; 14][..], &ethertype(ip), ip].concat()
+    }
+
+    // A connection closed by the server, a DNS query and an IPv6 connection captured after its handshake
+    fn conversation() -> Vec<(u64, Vec<u8>)> {
+        vec![
+            (100, tcp_packet(CLIENT, 50000, SERVER, 443, TCP_SYN)),
+            (100, tcp_packet(SERVER, 443, CLIENT, 50000, TCP_SYN | TCP_ACK)),
+            (101, tcp_packet(CLIENT, 50000, SERVER, 443, TCP_ACK)),
+            (120, udp_packet(SERVER, 53000, "8.8.8.8", 53)),
+            (150, tcp_packet(SERVER, 443, CLIENT, 50000, TCP_FIN | TCP_ACK)),
+            (151, tcp_packet(CLIENT, 50000, SERVER, 443, TCP_FIN | TCP_ACK)),
+            (160, tcp_packet("2001:db8::1", 8443, "2001:db8::2", 41000, TCP_ACK))
+        ]
+    }
+
+    fn u16_bytes(value: u16, big_endian: bool) -> [u8; 2] {
+        if big_endian { value.to_be_bytes() } else { value.to_le_bytes() }
+    }
+
+    fn u32_bytes(value: u32, big_endian: bool) -> [u8; 4] {
+        if big_endian { value.to_be_bytes() } else { value.to_le_bytes() }
+    }
+
+    fn pcap(magic: u32, big_endian: bool, link_type: u16, frame: fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        let mut bytes = [&u32_bytes(magic, big_endian)[..], &u16_bytes(2, big_endian), &u16_bytes(4, big_endian), &[0; 8], &u32_bytes(65535, big_endian), &u32_bytes(link_type as u32, big_endian)].concat();
+        for (timestamp, ip) in conversation() {
+            let data = frame(&ip);
+            let length = u32_bytes(data.len() as u32, big_endian);
+            bytes.extend([&u32_bytes(timestamp as u32, big_endian)[..], &u32_bytes(250, big_endian), &length, &length, &data].concat());
+        }
+        bytes
+    }
+
+    fn pcapng_block(block_type: u32, body: &[u8], big_endian: bool) -> Vec<u8> {
+        let padded = [body, &vec![0; (4 - body.len() % 4) % 4]].concat();
+        let length = u32_bytes(padded.len() as u32 + 12, big_endian);
+        [&u32_bytes(block_type, big_endian)[..], &length, &padded, &length].concat()
+    }
+
+    // 'tsresol' sets the if_tsresol option of the interface, timestamps are written in microseconds when it is None
+    fn pcapng(big_endian: bool, link_type: u16, tsresol: Option<u8>, frame: fn(&[u8]) -> Vec<u8>) -> Vec<u8> {
+        let section = [&u32_bytes(PCAPNG_BYTE_ORDER_MAGIC, big_endian)[..], &u16_bytes(1, big_endian), &u16_bytes(0, big_endian), &[0xff; 8]].concat();
+        let options = match tsresol {
+            Some(resolution) => [&u16_bytes(9, big_endian)[..], &u16_bytes(1, big_endian), &[resolution, 0, 0, 0], &[0; 4]].concat(),
+            None => Vec::new()
+        };
+        let interface = [&u16_bytes(link_type, big_endian)[..], &[0; 2], &u32_bytes(65535, big_endian), &options].concat();
+        let units_per_second = 10u64.pow(tsresol.unwrap_or(6) as u32);
+        let mut bytes = [pcapng_block(PCAPNG_SECTION_HEADER, &section, big_endian), pcapng_block(PCAPNG_INTERFACE_DESCRIPTION, &interface, big_endian)].concat();
+        for (timestamp, ip) in conversation() {
+            let data = frame(&ip);
+            let units = timestamp * units_per_second + 1234;
+            let length = u32_bytes(data.len() as u32, big_endian);
+            let body = [&u32_bytes(0, big_endian)[..], &u32_bytes((units >> 32) as u32, big_endian), &u32_bytes(units as u32, big_endian), &length, &length, &data].concat();
+            bytes.extend(pcapng_block(PCAPNG_ENHANCED_PACKET, &body, big_endian));
+        }
+        bytes
+    }
+
+    fn assert_conversation(capture: &[u8]) {
+        let sockets = parse_pcap("capture", capture, &[]);
+        assert_eq!(rows(&sockets), vec![
+            (0, "unknown", "TCP", SERVER, 443, Some(CLIENT), Some(50000), "TIME_WAIT"),
+            (0, "unknown", "UDP", "8.8.8.8", 53, Some(SERVER), Some(53000), " "),
+            (0, "unknown", "TCP", "2001:db8::1", 8443, Some("2001:db8::2"), Some(41000), "ESTABLISHED")
+        ]);
+        assert_eq!(sockets.iter().map(|s| s.uptime).collect::<Vec<_>>(), vec![60, 40, 0]);
+    }
+
+    #[test]
+    fn reads_pcap_in_either_byte_order() {
+        assert_conversation(&pcap(PCAP_MAGIC_MICROS, false, LINKTYPE_ETHERNET, ethernet_frame));
+        assert_conversation(&pcap(PCAP_MAGIC_MICROS, true, LINKTYPE_ETHERNET, ethernet_frame));
+        assert_conversation(&pcap(PCAP_MAGIC_NANOS, true, LINKTYPE_RAW, |ip| ip.to_vec()));
+    }
+
+    #[test]
+    fn reads_pcapng_in_either_byte_order() {
+        assert_conversation(&pcapng(false, LINKTYPE_ETHERNET, None, ethernet_frame));
+        assert_conversation(&pcapng(true, LINKTYPE_ETHERNET, None, ethernet_frame));
+        assert_conversation(&pcapng(false, LINKTYPE_LINUX_SLL, Some(9), linux_sll_frame));
+        assert_conversation(&pcapng(true, LINKTYPE_LINUX_SLL, Some(9), linux_sll_frame));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(read_pcap_packets(b"not a capture").is_none());
+        assert!(read_pcapng_packets(b"not a capture").is_none());
+    }
+
+    #[test]
+    fn names_processes_from_the_process_map() {
+        let capture = pcap(PCAP_MAGIC_MICROS, false, LINKTYPE_ETHERNET, ethernet_frame);
+        let server = Socket { process_name: "nginx".to_string(), pid: 1200, port: 443, protocol: "TCP", local_addr: "0.0.0.0".to_string(), ..Default::default() };
+        assert_eq!(rows(&parse_pcap("capture", &capture, &[server]))[0], (1200, "nginx", "TCP", SERVER, 443, Some(CLIENT), Some(50000), "TIME_WAIT"));
+
+        // A map taken on the client flips the flow, the client received the first FIN
+        let client = Socket { process_name: "curl".to_string(), pid: 4100, port: 50000, protocol: "TCP", local_addr: CLIENT.to_string(), ..Default::default() };
+        assert_eq!(rows(&parse_pcap("capture", &capture, &[client]))[0], (4100, "curl", "TCP", CLIENT, 50000, Some(SERVER), Some(443), "CLOSED"));
+    }
+
+    // State of the server side after the segments sent by the client (true) or the server (false)
+    fn server_state(segments: &[(bool, u8)]) -> String {
+        let segment = |from_client: bool, flags: u8| {
+            let (client, server) = ((CLIENT.parse().unwrap(), 50000), (SERVER.parse().unwrap(), 443));
+            let (src, dst) = if from_client { (client, server) } else { (server, client) };
+            Segment { protocol: "TCP", src, dst, flags }
+        };
+        let mut flow = Flow::new(&segment(segments[0].0, segments[0].1), 0);
+        for &(from_client, flags) in segments {
+            flow.update(&segment(from_client, flags));
+        }
+        assert_eq!(flow.local.1, 443);
+        flow.state()
+    }
+
+    #[test]
+    fn infers_tcp_states_from_flags() {
+        assert_eq!(server_state(&[(true, TCP_SYN)]), "SYN_RCVD");
+        assert_eq!(server_state(&[(true, TCP_SYN), (false, TCP_SYN | TCP_ACK)]), "SYN_RCVD");
+        assert_eq!(server_state(&[(true, TCP_SYN), (false, TCP_SYN | TCP_ACK), (true, TCP_ACK)]), "ESTABLISHED");
+        assert_eq!(server_state(&[(true, TCP_ACK), (true, TCP_FIN | TCP_ACK)]), "CLOSE_WAIT");
+        assert_eq!(server_state(&[(true, TCP_ACK), (false, TCP_FIN | TCP_ACK)]), "FIN_WAIT1");
+        assert_eq!(server_state(&[(true, TCP_ACK), (false, TCP_FIN | TCP_ACK), (true, TCP_FIN | TCP_ACK)]), "TIME_WAIT");
+        assert_eq!(server_state(&[(true, TCP_ACK), (true, TCP_FIN | TCP_ACK), (false, TCP_FIN | TCP_ACK)]), "CLOSED");
+        assert_eq!(server_state(&[(true, TCP_ACK), (false, TCP_RST)]), "CLOSED");
+    }
+
+    #[test]
+    fn reports_syn_sent_for_the_initiator() {
+        let syn = Segment { protocol: "TCP", src: (CLIENT.parse().unwrap(), 50000), dst: (SERVER.parse().unwrap(), 443), flags: TCP_SYN };
+        let mut flow = Flow::new(&syn, 0);
+        flow.update(&syn);
+        flow.flip();
+        assert_eq!(flow.state(), "SYN_SENT");
+    }
+}
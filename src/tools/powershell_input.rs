@@ -0,0 +1,229 @@
+use std::{collections::HashMap, time::{SystemTime, UNIX_EPOCH}};
+use serde_json::Value;
+use crate::tools::{civil_date::days_from_civil, connection_state_map::{map_tcp_state, normalize_state_name}, input::json_to_row, netstat_input::default_process_name, rpderror::RpdError, socket::Socket};
+
+fn parse_time_of_day(time: &str) -> Option<i64> {
+    let mut parts = time.split(':');
+    let hours: i64 = parts.next()?.parse().ok()?;
+    let minutes: i64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next().unwrap_or("0").parse().ok()?;
+    Some(hours * 3600 + minutes * 60 + seconds as i64)
+}
+
+// ISO 8601 ('2024-03-01T10:15:30.1234567+01:00') as written by ConvertTo-Json on PowerShell 7
+fn parse_iso_datetime(value: &str) -> Option<i64> {
+    let (date, time) = value.split_once(['T', ' '])?;
+    let mut date_parts = date.split('-');
+    let (year, month, day) = (date_parts.next()?.parse().ok()?, date_parts.next()?.parse().ok()?, date_parts.next()?.parse().ok()?);
+    let (time, offset) = match time.find(['Z', '+', '-']) {
+        Some(i) => (&time[..i], &time[i..]),
+        None => (time, "")
+    };
+    let offset_seconds = match offset.strip_prefix(['+', '-']) {
+        Some(hh_mm) => {
+            let sign = if offset.starts_with('-') { -1 } else { 1 };
+            sign * parse_time_of_day(hh_mm)?
+        }
+        None => 0
+    };
+    Some(days_from_civil(year, month, day) * 86400 + parse_time_of_day(time)? - offset_seconds)
+}
+
+// 'M/D/YYYY h:mm:ss AM' as written by Export-Csv with the en-US culture, the value carries no offset so it is read as UTC
+fn parse_us_datetime(value: &str) -> Option<i64> {
+    let mut parts = value.split_whitespace();
+    let mut date_parts = parts.next()?.split('/');
+    let (month, day, year) = (date_parts.next()?.parse().ok()?, date_parts.next()?.parse().ok()?, date_parts.next()?.parse().ok()?);
+    let mut seconds = parse_time_of_day(parts.next()?)?;
+    match parts.next() {
+        Some("PM") if seconds < 12 * 3600 => seconds += 12 * 3600,
+        Some("AM") if seconds >= 12 * 3600 => seconds -= 12 * 3600,
+        _ => {}
+    }
+    Some(days_from_civil(year, month, day) * 86400 + seconds)
+}
+
+// '/Date(1709288130123)/' on Windows PowerShell 5.1, ISO 8601 on PowerShell 7 and the culture format in CSV files
+pub fn parse_creation_time(value: &str) -> Option<i64> {
+    if let Some(millis) = value.strip_prefix("/Date(").and_then(|v| v.strip_suffix(")/")) {
+        let millis = millis.split(['+', '-']).find(|m| !m.is_empty())?;
+        return millis.parse::<i64>().ok().map(|m| m / 1000)
+    }
+    parse_iso_datetime(value).or_else(|| parse_us_datetime(value))
+}
+
+fn get_uptime(creation_time: Option<&str>) -> u64 {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+    creation_time.and_then(parse_creation_time).map_or(0, |created| now.saturating_sub(created).max(0) as u64)
+}
+
+// ConvertTo-Json writes the TcpState enum as its numeric value, which matches the MIB_TCP_STATE values, Export-Csv writes its name
+fn map_state(state: &str) -> String {
+    match state.parse::<u32>() {
+        Ok(state) => map_tcp_state(state),
+        Err(_) => normalize_state_name(state).unwrap_or_else(|| "UNKNOWN".to_string())
+    }
+}
+
+// Get-NetUDPEndpoint rows have no State nor remote end
+fn build_socket(row: &HashMap<String, String>) -> Option<Socket> {
+    let pid: u32 = row.get("OwningProcess").and_then(|p| p.parse().ok()).unwrap_or(0);
+    let is_tcp = row.contains_key("State") || row.contains_key("RemoteAddress");
+    Some(Socket {
+        process_name: default_process_name(pid),
+        pid,
+        port: row.get("LocalPort")?.parse().ok()?,
+        protocol: if is_tcp { "TCP" } else { "UDP" },
+        local_addr: row.get("LocalAddress")?.clone(),
+        remote_addr: if is_tcp { Some(row.get("RemoteAddress").cloned().unwrap_or_else(|| "0.0.0.0".to_string())) } else { None },
+        remote_port: if is_tcp { Some(row.get("RemotePort").and_then(|p| p.parse().ok()).unwrap_or(0)) } else { None },
+        state: if is_tcp { row.get("State").map_or("UNKNOWN".to_string(), |s| map_state(s)) } else { " ".to_string() },
+        executable_path: None,
+        uptime: get_uptime(row.get("CreationTime").map(String::as_str)),
+        ..Default::default()
+    })
+}
+
+// Splits a CSV line, double quotes inside quoted values are escaped by doubling them
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut values: Vec<String> = Vec::new();
+    let mut value = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                value.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => values.push(std::mem::take(&mut value)),
+            _ => value.push(c)
+        }
+    }
+    values.push(value);
+    values
+}
+
+// Export-Csv starts with an optional '#TYPE ...' line followed by the header
+fn csv_to_rows(content: &str) -> Vec<HashMap<String, String>> {
+    let mut lines = content.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+    let Some(header) = lines.next().map(split_csv_line) else { return Vec::new() };
+    lines.map(|line| header.iter().cloned().zip(split_csv_line(line)).filter(|(_, value)| !value.is_empty()).collect())
+        .collect()
+}
+
+// Output of 'Get-NetTCPConnection' or 'Get-NetUDPEndpoint' piped to ConvertTo-Json (a single object when only one row is exported) or Export-Csv
+pub fn parse_powershell(input: &str, content: &str) -> Vec<Socket> {
+    let trimmed = content.trim_start_matches('\u{feff}').trim_start();
+    let rows: Vec<HashMap<String, String>> = if trimmed.starts_with('[') || trimmed.starts_with('{') {
+        match serde_json::from_str::<Value>(trimmed) {
+            Ok(Value::Array(values)) => values.iter().filter_map(Value::as_object).map(json_to_row).collect(),
+            Ok(Value::Object(object)) => vec![json_to_row(&object)],
+            Ok(_) => Vec::new(),
+            Err(e) => RpdError::ParseInputErr(input.to_string(), e.to_string()).handle()
+        }
+    } else {
+        csv_to_rows(trimmed)
+    };
+    rows.iter().filter_map(build_socket).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::input::tests::rows;
+
+    // 'Get-NetTCPConnection | ConvertTo-Json' on Windows PowerShell 5.1, the state is written as its numeric value
+    const TCP_JSON: &str = r#"[
+    {
+        "LocalAddress": "0.0.0.0",
+        "LocalPort": 445,
+        "RemoteAddress": "0.0.0.0",
+        "RemotePort": 0,
+        "State": 2,
+        "OwningProcess": 4,
+        "CreationTime": "/Date(1709288130123)/",
+        "CimClass": { "CimClassName": "MSFT_NetTCPConnection" }
+    },
+    {
+        "LocalAddress": "10.0.0.5",
+        "LocalPort": 49822,
+        "RemoteAddress": "93.184.216.34",
+        "RemotePort": 443,
+        "State": 5,
+        "OwningProcess": 3100,
+        "CreationTime": "2024-03-01T10:15:30.1234567+01:00"
+    },
+    {
+        "LocalAddress": "::",
+        "LocalPort": 135,
+        "RemoteAddress": "::",
+        "RemotePort": 0,
+        "State": 2,
+        "OwningProcess": 1032
+    }
+]"#;
+
+    // A single endpoint is exported as an object instead of an array
+    const UDP_JSON: &str = "\u{feff}{ \"LocalAddress\": \"0.0.0.0\", \"LocalPort\": 5353, \"OwningProcess\": 720, \"CreationTime\": \"2024-03-01T10:15:30Z\" }";
+
+    // 'Get-NetTCPConnection | Export-Csv' with the en-US culture
+    const TCP_CSV: &str = r#"#TYPE Microsoft.Management.Infrastructure.CimInstance#ROOT/StandardCimv2/MSFT_NetTCPConnection
+"LocalAddress","LocalPort","RemoteAddress","RemotePort","State","OwningProcess","CreationTime","InstanceID"
+"10.0.0.5","443","203.0.113.10","51234","Established","1200","3/1/2024 10:15:30 AM","10.0.0.5++443++203.0.113.10++51234"
+"10.0.0.5","443","198.51.100.7","60211","TimeWait","0","",""
+"0.0.0.0","80","0.0.0.0","0","Listen","1200","3/1/2024 1:15:30 PM","0.0.0.0++80,0.0.0.0++0"
+"#;
+
+    const UDP_CSV: &str = "\"LocalAddress\",\"LocalPort\",\"OwningProcess\"\n\"::\",\"5355\",\"1400\"\n";
+
+    #[test]
+    fn parses_json_arrays() {
+        let sockets = parse_powershell("tcp.json", TCP_JSON);
+        assert_eq!(rows(&sockets), vec![
+            (4, "SYSTEM", "TCP", "0.0.0.0", 445, Some("0.0.0.0"), Some(0), "LISTEN"),
+            (3100, "unknown", "TCP", "10.0.0.5", 49822, Some("93.184.216.34"), Some(443), "ESTABLISHED"),
+            (1032, "unknown", "TCP", "::", 135, Some("::"), Some(0), "LISTEN")
+        ]);
+        assert!(sockets[0].uptime > 0 && sockets[1].uptime > 0);
+        assert_eq!(sockets[2].uptime, 0);
+    }
+
+    #[test]
+    fn parses_single_json_objects() {
+        let sockets = parse_powershell("udp.json", UDP_JSON);
+        assert_eq!(rows(&sockets), vec![(720, "unknown", "UDP", "0.0.0.0", 5353, None, None, " ")]);
+        assert!(sockets[0].uptime > 0);
+    }
+
+    #[test]
+    fn parses_csv_exports() {
+        let sockets = parse_powershell("tcp.csv", TCP_CSV);
+        assert_eq!(rows(&sockets), vec![
+            (1200, "unknown", "TCP", "10.0.0.5", 443, Some("203.0.113.10"), Some(51234), "ESTABLISHED"),
+            (0, "unknown", "TCP", "10.0.0.5", 443, Some("198.51.100.7"), Some(60211), "TIME_WAIT"),
+            (1200, "unknown", "TCP", "0.0.0.0", 80, Some("0.0.0.0"), Some(0), "LISTEN")
+        ]);
+        assert_eq!(sockets[1].uptime, 0);
+        assert_eq!(rows(&parse_powershell("udp.csv", UDP_CSV)), vec![(1400, "unknown", "UDP", "::", 5355, None, None, " ")]);
+    }
+
+    #[test]
+    fn splits_quoted_csv_values() {
+        assert_eq!(split_csv_line(r#""a,b","say ""hi""",,c"#), vec!["a,b", "say \"hi\"", "", "c"]);
+    }
+
+    #[test]
+    fn parses_creation_times() {
+        assert_eq!(parse_creation_time("/Date(1709288130123)/"), Some(1709288130));
+        assert_eq!(parse_creation_time("/Date(1709288130123+0100)/"), Some(1709288130));
+        assert_eq!(parse_creation_time("2024-03-01T10:15:30Z"), Some(1709288130));
+        assert_eq!(parse_creation_time("2024-03-01T10:15:30.1234567+01:00"), Some(1709284530));
+        assert_eq!(parse_creation_time("2024-03-01T10:15:30-05:30"), Some(1709307930));
+        assert_eq!(parse_creation_time("3/1/2024 10:15:30 AM"), Some(1709288130));
+        assert_eq!(parse_creation_time("3/1/2024 12:05:00 AM"), Some(1709251500));
+        assert_eq!(parse_creation_time("3/1/2024 1:15:30 PM"), Some(1709298930));
+        assert_eq!(parse_creation_time("yesterday"), None);
+    }
+}
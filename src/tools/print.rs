@@ -1,5 +1,6 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use colored::{ColoredString, Colorize};
-use crate::tools::{print_utils::*, socket::Socket, stats::get_socket_stats};
+use crate::tools::{args::Args, print_utils::*, process_tree::ProcessTree, socket::Socket, stats::get_socket_stats};
 
 /////////////////// Socket Table
 
@@ -8,12 +9,12 @@ pub fn print_table_line(widths: &[usize], fields: &Option<Vec<String>>) {
     if let Some(fields) = fields {
         fields.iter().for_each(|field| {
             if let Some(idx) = FIELD_WIDTH_MAP.get(field.as_str()) {
-                if line_string.chars().last() != Some('+') { line_string.push('+'); }
+                if !line_string.ends_with('+') { line_string.push('+'); }
                 line_string.push_str(format!("{}+", "-".repeat(widths[*idx])).as_str());
             }
         });
     } else {
-        line_string = widths
+        line_string = widths[..DEFAULT_COLUMNS]
         .iter()
         .map(|w| format!("+{}", "-".repeat(*w)))
         .collect::<String>() + "+";
@@ -22,20 +23,21 @@ pub fn print_table_line(widths: &[usize], fields: &Option<Vec<String>>) {
 }
 
 pub fn print_socket_row(socket: &Socket, widths: &[usize], compact: bool, fields: &Option<Vec<String>>, uptime_arg: &Option<String>) {
-    let port_str = format!("{}:{}", socket.port, socket.remote_port.map_or('-'.to_string(), |p| p.to_string()));
+    // Sockets with data piling up in their queues get their port and queue sizes highlighted
+    let queued = has_queued_data(socket);
+    let port_str = if queued { format_port(socket).bold().yellow() } else { format_port(socket).normal() };
     let remote_addr = socket.remote_addr.as_deref().unwrap_or(" ");
-    let protocol_string = match socket.protocol {
-        "UDP" => "UDP/IP".bold().blue(),
-        "TCP" => "TCP/IP".bold().green(),
-        _ => "unknown".bold().red()
-    };
+    let protocol_string = map_protocol_color(socket.protocol, get_protocol_label(socket));
+    let local_addr = get_local_address_label(socket);
 
     let uptime_str = get_formatted_uptime(uptime_arg, socket.uptime);
+    let skmem_str = format_skmem(&socket.skmem);
+    let peer_str = format_peer(socket);
 
     let process_name = match socket.process_name.as_str() {
         "SYSTEM" => "SYSTEM".bold().cyan(),
         "unknown" => "unknown".bold().red(),
-        _ => ansi_hyperlink(&socket.process_name, socket.executable_path.as_deref(), widths[1]).bold()
+        _ => map_process_color(socket.protocol, ansi_hyperlink(&socket.process_name, socket.executable_path.as_deref(), widths[1]))
     };
     let mut socket_row_str: String = String::new();
 
@@ -47,29 +49,51 @@ pub fn print_socket_row(socket: &Socket, widths: &[usize], compact: bool, fields
                 "process-name" => socket_row_str.push_str(format!("{:>process_name_w$}|", process_name, process_name_w = widths[PROCESS_IDX]).as_str()),
                 "port" => socket_row_str.push_str(format!("{:^port_w$}|", port_str, port_w = widths[PORT_IDX]).as_str()),
                 "protocol" => socket_row_str.push_str(format!("{:^proto_w$}|", protocol_string, proto_w = widths[PROTOCOL_IDX]).as_str()),
-                "local-address" => socket_row_str.push_str(format!("{:>local_addr_w$}|", socket.local_addr, local_addr_w = widths[LOCAL_ADDR_IDX]).as_str()),
+                "local-address" => socket_row_str.push_str(format!("{:>local_addr_w$}|", local_addr, local_addr_w = widths[LOCAL_ADDR_IDX]).as_str()),
                 "remote-address" => socket_row_str.push_str(format!("{:>remote_addr_w$}|", remote_addr, remote_addr_w = widths[REMOTE_ADDR_IDX]).as_str()),
                 "state" => socket_row_str.push_str(format!("{:^state_w$}|", map_state_color(&socket.state), state_w = widths[STATE_IDX]).as_str()),
                 "uptime" => socket_row_str.push_str(format!("{:^uptime_w$}|", uptime_str, uptime_w = widths[UPTIME_IDX]).as_str()),
+                "rtt" => socket_row_str.push_str(format!("{:^rtt_w$}|", format_rtt(socket.rtt), rtt_w = widths[RTT_IDX]).as_str()),
+                "rtt-var" => socket_row_str.push_str(format!("{:^rtt_var_w$}|", format_rtt(socket.rtt_var), rtt_var_w = widths[RTT_VAR_IDX]).as_str()),
+                "cwnd" => socket_row_str.push_str(format!("{:^cwnd_w$}|", format_optional(socket.cwnd), cwnd_w = widths[CWND_IDX]).as_str()),
+                "retransmits" => socket_row_str.push_str(format!("{:^retransmits_w$}|", format_optional(socket.retransmits), retransmits_w = widths[RETRANSMITS_IDX]).as_str()),
+                "mss" => socket_row_str.push_str(format!("{:^mss_w$}|", format_optional(socket.mss), mss_w = widths[MSS_IDX]).as_str()),
+                "congestion" => socket_row_str.push_str(format!("{:^congestion_w$}|", format_optional(socket.congestion.as_deref()), congestion_w = widths[CONGESTION_IDX]).as_str()),
+                "skmem" => socket_row_str.push_str(format!("{:>skmem_w$}|", skmem_str, skmem_w = widths[SKMEM_IDX]).as_str()),
+                "peer" => socket_row_str.push_str(format!("{:>peer_w$}|", peer_str, peer_w = widths[PEER_IDX]).as_str()),
+                "interface" => socket_row_str.push_str(format!("{:^interface_w$}|", format_interface(socket.interface.as_deref()), interface_w = widths[INTERFACE_IDX]).as_str()),
+                "netns" => socket_row_str.push_str(format!("{:^netns_w$}|", format_optional(socket.netns), netns_w = widths[NETNS_IDX]).as_str()),
+                "container" => socket_row_str.push_str(format!("{:>container_w$}|", format_container(socket), container_w = widths[CONTAINER_IDX]).as_str()),
+                "unit" => socket_row_str.push_str(format!("{:>unit_w$}|", format_optional(socket.unit.as_deref()), unit_w = widths[UNIT_IDX]).as_str()),
+                "orig-bytes" => socket_row_str.push_str(format!("{:>orig_bytes_w$}|", format_optional(socket.orig_bytes), orig_bytes_w = widths[ORIG_BYTES_IDX]).as_str()),
+                "resp-bytes" => socket_row_str.push_str(format!("{:>resp_bytes_w$}|", format_optional(socket.resp_bytes), resp_bytes_w = widths[RESP_BYTES_IDX]).as_str()),
+                "cmdline" => socket_row_str.push_str(format!("{:<cmdline_w$}|", format_cmdline(socket.cmdline.as_deref(), widths[CMDLINE_IDX]), cmdline_w = widths[CMDLINE_IDX]).as_str()),
+                "user" => socket_row_str.push_str(format!("{:>user_w$}|", format_optional(socket.user.as_deref()), user_w = widths[USER_IDX]).as_str()),
+                "ppid" => socket_row_str.push_str(format!("{:^ppid_w$}|", format_optional(socket.ppid), ppid_w = widths[PPID_IDX]).as_str()),
+                "start-time" => socket_row_str.push_str(format!("{:^start_time_w$}|", format_start_time(socket.start_time), start_time_w = widths[START_TIME_IDX]).as_str()),
+                "recv-q" => socket_row_str.push_str(format!("{:>recv_q_w$}|", format_queue(socket.recv_q, queued), recv_q_w = widths[RECV_Q_IDX]).as_str()),
+                "send-q" => socket_row_str.push_str(format!("{:>send_q_w$}|", format_queue(socket.send_q, queued && socket.state != "LISTEN"), send_q_w = widths[SEND_Q_IDX]).as_str()),
                 _ => {}
             }
         });
     } else {
-        socket_row_str = format!("{:^pid_w$}|{:>process_name_w$}|{:^port_w$}|{:^proto_w$}|{:>local_addr_w$}|{:>remote_addr_w$}|{:^state_w$}|{:^uptime_w$}",
+        let addresses_str = if spans_address_columns(socket) {
+            format!("{:>path_w$}", local_addr, path_w = widths[LOCAL_ADDR_IDX] + widths[REMOTE_ADDR_IDX] + 1)
+        } else {
+            format!("{:>local_addr_w$}|{:>remote_addr_w$}", local_addr, remote_addr, local_addr_w = widths[LOCAL_ADDR_IDX], remote_addr_w = widths[REMOTE_ADDR_IDX])
+        };
+        socket_row_str = format!("{:^pid_w$}|{:>process_name_w$}|{:^port_w$}|{:^proto_w$}|{}|{:^state_w$}|{:^uptime_w$}",
             socket.pid,
             process_name, 
             port_str, 
             protocol_string,
-            socket.local_addr,
-            remote_addr,
+            addresses_str,
             map_state_color(&socket.state),
             uptime_str,
             pid_w = widths[PID_IDX],
             process_name_w = widths[PROCESS_IDX],
             port_w = widths[PORT_IDX],
             proto_w = widths[PROTOCOL_IDX],
-            local_addr_w = widths[LOCAL_ADDR_IDX],
-            remote_addr_w = widths[REMOTE_ADDR_IDX],
             state_w = widths[STATE_IDX],
             uptime_w = widths[UPTIME_IDX]
         );
@@ -78,7 +102,7 @@ pub fn print_socket_row(socket: &Socket, widths: &[usize], compact: bool, fields
     let row_str = if compact {
         socket_row_str.replace("|", "")
     } else {
-        if socket_row_str.chars().last() != Some('|') { socket_row_str.push('|'); }
+        if !socket_row_str.ends_with('|') { socket_row_str.push('|'); }
         format!("|{}", socket_row_str)
     };
 
@@ -90,7 +114,7 @@ pub fn print_socket_table_header(widths: &[usize], compact: bool, fields: &Optio
     let mut header = String::new();
     if let Some(fields) = fields {
         for field in fields {
-            if header.chars().last() != Some('|') { header.push('|') ;}
+            if !header.ends_with('|') { header.push('|') ;}
             let column_header = match field.to_lowercase().as_str() {
                 "pid" => format!("{:^pid_w$}|", "PID".bold(), pid_w = widths[PID_IDX]),
                 "process-name" => format!("{:^process_name_w$}|", "Process Name".bold(), process_name_w = widths[PROCESS_IDX]),
@@ -100,6 +124,26 @@ pub fn print_socket_table_header(widths: &[usize], compact: bool, fields: &Optio
                 "remote-address" => format!("{:^remote_addr_w$}|", "Remote Address".bold(), remote_addr_w = widths[REMOTE_ADDR_IDX]),
                 "state" => format!("{:^state_w$}|", "State".bold(), state_w = widths[STATE_IDX]),
                 "uptime" => format!("{:^uptime_w$}|", "Uptime".bold(), uptime_w = widths[UPTIME_IDX]),
+                "rtt" => format!("{:^rtt_w$}|", "RTT".bold(), rtt_w = widths[RTT_IDX]),
+                "rtt-var" => format!("{:^rtt_var_w$}|", "RTT Var".bold(), rtt_var_w = widths[RTT_VAR_IDX]),
+                "cwnd" => format!("{:^cwnd_w$}|", "Cwnd".bold(), cwnd_w = widths[CWND_IDX]),
+                "retransmits" => format!("{:^retransmits_w$}|", "Retransmits".bold(), retransmits_w = widths[RETRANSMITS_IDX]),
+                "mss" => format!("{:^mss_w$}|", "MSS".bold(), mss_w = widths[MSS_IDX]),
+                "congestion" => format!("{:^congestion_w$}|", "Congestion".bold(), congestion_w = widths[CONGESTION_IDX]),
+                "skmem" => format!("{:^skmem_w$}|", "Socket Memory".bold(), skmem_w = widths[SKMEM_IDX]),
+                "peer" => format!("{:^peer_w$}|", "Peer".bold(), peer_w = widths[PEER_IDX]),
+                "interface" => format!("{:^interface_w$}|", "Interface".bold(), interface_w = widths[INTERFACE_IDX]),
+                "netns" => format!("{:^netns_w$}|", "Netns".bold(), netns_w = widths[NETNS_IDX]),
+                "container" => format!("{:^container_w$}|", "Container".bold(), container_w = widths[CONTAINER_IDX]),
+                "unit" => format!("{:^unit_w$}|", "Unit".bold(), unit_w = widths[UNIT_IDX]),
+                "orig-bytes" => format!("{:^orig_bytes_w$}|", "Orig Bytes".bold(), orig_bytes_w = widths[ORIG_BYTES_IDX]),
+                "resp-bytes" => format!("{:^resp_bytes_w$}|", "Resp Bytes".bold(), resp_bytes_w = widths[RESP_BYTES_IDX]),
+                "cmdline" => format!("{:^cmdline_w$}|", "Command Line".bold(), cmdline_w = widths[CMDLINE_IDX]),
+                "user" => format!("{:^user_w$}|", "User".bold(), user_w = widths[USER_IDX]),
+                "ppid" => format!("{:^ppid_w$}|", "PPID".bold(), ppid_w = widths[PPID_IDX]),
+                "start-time" => format!("{:^start_time_w$}|", "Start Time".bold(), start_time_w = widths[START_TIME_IDX]),
+                "recv-q" => format!("{:^recv_q_w$}|", "Recv-Q".bold(), recv_q_w = widths[RECV_Q_IDX]),
+                "send-q" => format!("{:^send_q_w$}|", "Send-Q".bold(), send_q_w = widths[SEND_Q_IDX]),
                 _ => continue,
             };
             header.push_str(&column_header);
@@ -134,8 +178,70 @@ pub fn print_socket_table_header(widths: &[usize], compact: bool, fields: &Optio
     }
 }
 
+/////////////////// Process Tree
+
+struct SocketTree<'a> {
+    tree: ProcessTree,
+    children: BTreeMap<u32, BTreeSet<u32>>,
+    sockets: HashMap<u32, Vec<&'a Socket>>
+}
+
+fn format_tree_process(socket_tree: &SocketTree, pid: u32) -> String {
+    let first_socket = socket_tree.sockets.get(&pid).and_then(|sockets| sockets.first());
+    let name = socket_tree.tree.get_name(pid)
+        .or(first_socket.map(|s| s.process_name.as_str()))
+        .unwrap_or("unknown");
+    let name = if name == "unknown" { name.bold().red() } else { name.bold() };
+    match first_socket.and_then(|s| s.user.as_deref()) {
+        Some(user) => format!("{} ({}) {}", name, pid.to_string().cyan(), user.dimmed()),
+        None => format!("{} ({})", name, pid.to_string().cyan())
+    }
+}
+
+fn print_tree_process(socket_tree: &SocketTree, pid: u32, prefix: &str, connector: &str, child_prefix: &str, uptime_arg: &Option<String>) {
+    println!("{}{}{}", prefix, connector, format_tree_process(socket_tree, pid));
+    let child_prefix = format!("{}{}", prefix, child_prefix);
+    let sockets = socket_tree.sockets.get(&pid).map(Vec::as_slice).unwrap_or_default();
+    let children: Vec<u32> = socket_tree.children.get(&pid).map(|c| c.iter().copied().collect()).unwrap_or_default();
+    let item_count = sockets.len() + children.len();
+
+    // Sockets of the process come before its child processes
+    for (i, socket) in sockets.iter().enumerate() {
+        let connector = if i + 1 == item_count { "└── " } else { "├── " };
+        println!("{}{}{}", child_prefix, connector, format_tree_socket(socket, uptime_arg));
+    }
+    for (i, child) in children.iter().enumerate() {
+        let is_last = sockets.len() + i + 1 == item_count;
+        let (connector, next_prefix) = if is_last { ("└── ", "    ") } else { ("├── ", "│   ") };
+        print_tree_process(socket_tree, *child, &child_prefix, connector, next_prefix, uptime_arg);
+    }
+}
+
+// Only the ancestors of processes owning a socket are shown, each root is the oldest known ancestor
+pub fn print_socket_tree(socket_table: &[Socket], args: &Args) {
+    let mut socket_tree = SocketTree {
+        tree: ProcessTree::new(socket_table, args.reads_live_system()),
+        children: BTreeMap::new(),
+        sockets: HashMap::new()
+    };
+    let mut roots: BTreeSet<u32> = BTreeSet::new();
+    for socket in socket_table {
+        let ancestry = socket_tree.tree.get_ancestry(socket.pid);
+        roots.insert(ancestry[0]);
+        for pair in ancestry.windows(2) {
+            socket_tree.children.entry(pair[0]).or_default().insert(pair[1]);
+        }
+        socket_tree.sockets.entry(socket.pid).or_default().push(socket);
+    }
+    for root in roots {
+        print_tree_process(&socket_tree, root, "", "", "", &args.uptime_format);
+    }
+}
+
 /////////////////// Stats
 
+const UDP_STATS_LINE: usize = 4;
+
 fn print_padded_line(width: usize, left_str: ColoredString, right_str: ColoredString) {
     let left_len = visible_length(&left_str);
     let right_len = visible_length(&right_str);
@@ -143,10 +249,21 @@ fn print_padded_line(width: usize, left_str: ColoredString, right_str: ColoredSt
     println!("{}{}{}", left_str, " ".repeat(middle_spaces), right_str);
 }
 
-pub fn print_socket_stats(socket_table: &Vec<Socket>) {
+fn push_group_lines(stats_strings: &mut Vec<(ColoredString, ColoredString)>, title: &str, ungrouped: (&str, usize), groups: &[(String, usize)]) {
+    if let Some((_, right)) = stats_strings.last_mut() {
+        *right = format!("{}\n\n", right).normal();
+    }
+    stats_strings.push((String::from(title).bold().underline(), String::from("\n").normal()));
+    stats_strings.push((format!("  - {}:", ungrouped.0).bold(), format!("{}", ungrouped.1).bold().blue()));
+    groups.iter().for_each(|(label, count)| {
+        stats_strings.push((format!("  - {}:", label).bold(), format!("{}", count).bold().blue()));
+    });
+}
+
+pub fn print_socket_stats(socket_table: &[Socket]) {
     let stats = get_socket_stats(socket_table);
     let mut output_width: usize = 0;
-    let stats_strings = [
+    let mut stats_strings = vec![
         (String::from("Connections:").bold().underline(), format!("{}\n", stats.connection_count).bold().blue()),
         (String::from("  - TCP:").bold(), format!("{}", stats.tcp_count).bold().blue()),
         (String::from("      ESTABLISHED:").bold().green(), format!("{}", stats.established_count).bold().green()),
@@ -162,10 +279,46 @@ pub fn print_socket_stats(socket_table: &Vec<Socket>) {
         (String::from("  - Remote addresses:").bold(), format!("{}", stats.remote_address_count).bold().blue()),
         (String::from("  - Top remote address:").bold(), format!("{} ({})\n\n", stats.top_remote_address.0.bold().cyan(), stats.top_remote_address.1.to_string().bold().blue()).normal()),
         (String::from("Uptimes:").bold().underline(), String::from("\n").normal()),
-        (String::from("  - Youngest connection:").bold(), format!("{}", stats.youngest_connection).bold().green()),
-        (String::from("  - Oldest connection:").bold(), format!("{}", stats.oldest_connection).bold().red())
+        (String::from("  - Youngest connection:").bold(), stats.youngest_connection.to_string().bold().green()),
+        (String::from("  - Oldest connection:").bold(), stats.oldest_connection.to_string().bold().red())
     ];
 
+    // Sockets per container and per systemd unit, only when at least one socket could be attributed
+    if !stats.containers.is_empty() {
+        push_group_lines(&mut stats_strings, "Containers:", ("Host", stats.host_count), &stats.containers);
+    }
+    if !stats.units.is_empty() {
+        push_group_lines(&mut stats_strings, "Units:", ("No unit", stats.no_unit_count), &stats.units);
+    }
+
+    // Byte counters only come from imported network logs
+    if let Some((orig_bytes, resp_bytes)) = stats.traffic {
+        if let Some((_, right)) = stats_strings.last_mut() {
+            *right = format!("{}\n\n", right).normal();
+        }
+        stats_strings.push((String::from("Traffic:").bold().underline(), String::from("\n").normal()));
+        stats_strings.push((String::from("  - Originator bytes:").bold(), format!("{}", orig_bytes).bold().blue()));
+        stats_strings.push((String::from("  - Responder bytes:").bold(), format!("{}", resp_bytes).bold().blue()));
+        stats_strings.push((String::from("  - Top talker:").bold(), format!("{} ({})", stats.top_talker.0.bold().cyan(), stats.top_talker.1.to_string().bold().blue()).normal()));
+    }
+
+    // Protocol lines that only apply to Linux are shown only when there are sockets to count, the last protocol line closes the section
+    let mut protocol_lines = Vec::new();
+    if stats.unix_count > 0 {
+        protocol_lines.push((String::from("  - UNIX:").bold(), format!("{}", stats.unix_count).bold().magenta()));
+    }
+    if stats.raw_count > 0 {
+        protocol_lines.push((String::from("  - RAW:").bold(), format!("{}", stats.raw_count).bold().yellow()));
+    }
+    if stats.packet_count > 0 {
+        protocol_lines.push((String::from("  - PACKET:").bold(), format!("{}", stats.packet_count).bold().red()));
+    }
+    if let Some((left, right)) = protocol_lines.pop() {
+        stats_strings[UDP_STATS_LINE].1 = format!("{}", stats.udp_count).bold().blue();
+        protocol_lines.push((left, format!("{}\n\n", right).normal()));
+        stats_strings.splice(UDP_STATS_LINE + 1..UDP_STATS_LINE + 1, protocol_lines);
+    }
+
     stats_strings.iter().for_each(|(left, right)| {
         output_width = std::cmp::max(output_width, visible_length(left) + visible_length(right) + 4);
     });
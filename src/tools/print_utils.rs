@@ -1,8 +1,10 @@
 use std::{collections::HashMap, path::Path, sync::LazyLock};
 use colored::{ColoredString, Colorize};
-use crate::tools::rpderror::RpdError;
+use crate::tools::{civil_date::civil_from_days, connection_state_map::{map_ethertype, map_ip_protocol}, rpderror::RpdError, socket::{Socket, SocketMemory}};
 
-pub const OUTPUT_FIELDS: [&str; 8] = ["pid", "process-name", "port", "protocol", "local-address", "remote-address", "state", "uptime"];
+pub const OUTPUT_FIELDS: [&str; 28] = ["pid", "process-name", "port", "protocol", "local-address", "remote-address", "state", "uptime", "rtt", "rtt-var", "cwnd", "retransmits", "mss", "congestion", "skmem", "peer", "interface", "netns", "container", "unit", "orig-bytes", "resp-bytes", "cmdline", "user", "ppid", "start-time", "recv-q", "send-q"];
+// Columns shown when '--fields' is not provided
+pub const DEFAULT_COLUMNS: usize = 8;
 pub const PID_IDX: usize = 0;
 pub const PROCESS_IDX: usize = 1;
 pub const PORT_IDX: usize = 2;
@@ -11,6 +13,26 @@ pub const LOCAL_ADDR_IDX: usize = 4;
 pub const REMOTE_ADDR_IDX: usize = 5;
 pub const STATE_IDX: usize = 6;
 pub const UPTIME_IDX: usize = 7;
+pub const RTT_IDX: usize = 8;
+pub const RTT_VAR_IDX: usize = 9;
+pub const CWND_IDX: usize = 10;
+pub const RETRANSMITS_IDX: usize = 11;
+pub const MSS_IDX: usize = 12;
+pub const CONGESTION_IDX: usize = 13;
+pub const SKMEM_IDX: usize = 14;
+pub const PEER_IDX: usize = 15;
+pub const INTERFACE_IDX: usize = 16;
+pub const NETNS_IDX: usize = 17;
+pub const CONTAINER_IDX: usize = 18;
+pub const UNIT_IDX: usize = 19;
+pub const ORIG_BYTES_IDX: usize = 20;
+pub const RESP_BYTES_IDX: usize = 21;
+pub const CMDLINE_IDX: usize = 22;
+pub const USER_IDX: usize = 23;
+pub const PPID_IDX: usize = 24;
+pub const START_TIME_IDX: usize = 25;
+pub const RECV_Q_IDX: usize = 26;
+pub const SEND_Q_IDX: usize = 27;
 
 pub static FIELD_WIDTH_MAP: LazyLock<HashMap<&str, usize>> = LazyLock::new(|| {
     let mut map: HashMap<&str, usize> = HashMap::new();
@@ -22,6 +44,26 @@ pub static FIELD_WIDTH_MAP: LazyLock<HashMap<&str, usize>> = LazyLock::new(|| {
     map.insert("remote-address", REMOTE_ADDR_IDX);
     map.insert("state", STATE_IDX);
     map.insert("uptime", UPTIME_IDX);
+    map.insert("rtt", RTT_IDX);
+    map.insert("rtt-var", RTT_VAR_IDX);
+    map.insert("cwnd", CWND_IDX);
+    map.insert("retransmits", RETRANSMITS_IDX);
+    map.insert("mss", MSS_IDX);
+    map.insert("congestion", CONGESTION_IDX);
+    map.insert("skmem", SKMEM_IDX);
+    map.insert("peer", PEER_IDX);
+    map.insert("interface", INTERFACE_IDX);
+    map.insert("netns", NETNS_IDX);
+    map.insert("container", CONTAINER_IDX);
+    map.insert("unit", UNIT_IDX);
+    map.insert("orig-bytes", ORIG_BYTES_IDX);
+    map.insert("resp-bytes", RESP_BYTES_IDX);
+    map.insert("cmdline", CMDLINE_IDX);
+    map.insert("user", USER_IDX);
+    map.insert("ppid", PPID_IDX);
+    map.insert("start-time", START_TIME_IDX);
+    map.insert("recv-q", RECV_Q_IDX);
+    map.insert("send-q", SEND_Q_IDX);
     map
 });
 
@@ -66,8 +108,149 @@ pub fn get_formatted_uptime(uptime_arg: &Option<String>, socket_uptime: u64) ->
     }
 }
 
-pub fn map_state_color(state: &String) -> ColoredString{
-    match state.as_str() {
+pub fn get_protocol_label(socket: &Socket) -> String {
+    match socket.protocol {
+        "TCP" | "UDP" => format!("{}/IP", socket.protocol),
+        "RAW" => "RAW/IP".to_string(),
+        "UNIX" | "PACKET" => format!("{}/{}", socket.protocol, socket.socket_type.as_deref().unwrap_or("UNKNOWN")),
+        _ => "unknown".to_string()
+    }
+}
+
+// Unix socket paths and packet socket interfaces take the place of both address columns
+pub fn spans_address_columns(socket: &Socket) -> bool {
+    matches!(socket.protocol, "UNIX" | "PACKET")
+}
+
+// Unnamed Unix sockets have no path and packet sockets bound to no interface receive from all of them
+pub fn get_local_address_label(socket: &Socket) -> &str {
+    let label = match socket.protocol {
+        "PACKET" => socket.interface.as_deref().unwrap_or_default(),
+        _ => socket.local_addr.as_str()
+    };
+    if label.is_empty() && spans_address_columns(socket) { "*" } else { label }
+}
+
+// Raw and packet sockets have no port, the IP protocol number or ethertype they are bound to is shown instead
+pub fn format_port(socket: &Socket) -> String {
+    match (socket.protocol, socket.protocol_number) {
+        ("UNIX", _) => "-".to_string(),
+        ("RAW", Some(number)) => format!("{} ({})", map_ip_protocol(number), number),
+        ("PACKET", Some(number)) => format!("{} (0x{:04x})", map_ethertype(number), number),
+        ("RAW" | "PACKET", None) => "-".to_string(),
+        _ => format!("{}:{}", socket.port, socket.remote_port.map_or('-'.to_string(), |p| p.to_string()))
+    }
+}
+
+pub fn format_interface(interface: Option<&str>) -> &str {
+    interface.unwrap_or("*")
+}
+
+// Same 'PID/Program name' layout used by netstat
+pub fn format_peer(socket: &Socket) -> String {
+    match (socket.peer_pid, &socket.peer_process) {
+        (Some(pid), Some(process)) if pid != 0 => format!("{}/{}", pid, process),
+        (Some(_), _) => "unknown".to_string(),
+        _ => "-".to_string()
+    }
+}
+
+pub fn format_container(socket: &Socket) -> String {
+    socket.container.as_ref().map_or("-".to_string(), |c| c.label())
+}
+
+fn format_endpoint(addr: &str, port: u16) -> String {
+    if addr.contains(':') { format!("[{}]:{}", addr, port) } else { format!("{}:{}", addr, port) }
+}
+
+// One line per socket in '--tree' mode, the remote end is left out for listeners and unconnected sockets
+pub fn format_tree_socket(socket: &Socket, uptime_arg: &Option<String>) -> String {
+    let mut parts: Vec<String> = vec![map_protocol_color(socket.protocol, get_protocol_label(socket)).to_string()];
+    match socket.protocol {
+        "TCP" | "UDP" => {
+            parts.push(format_endpoint(&socket.local_addr, socket.port));
+            if let (Some(addr), Some(port)) = (&socket.remote_addr, socket.remote_port) && port != 0 {
+                parts.push(format!("-> {}", format_endpoint(addr, port)));
+            }
+        }
+        "RAW" | "PACKET" => parts.push(format!("{} {}", format_port(socket), get_local_address_label(socket))),
+        _ => parts.push(get_local_address_label(socket).to_string())
+    }
+    if !socket.state.trim().is_empty() {
+        parts.push(map_state_color(&socket.state).to_string());
+    }
+    parts.push(format!("({})", get_formatted_uptime(uptime_arg, socket.uptime)));
+    parts.join(" ")
+}
+
+// The send queue of a listener holds its maximum backlog, so only the receive queue tells whether connections are piling up there
+pub fn has_queued_data(socket: &Socket) -> bool {
+    socket.recv_q.is_some_and(|q| q > 0) || (socket.state != "LISTEN" && socket.send_q.is_some_and(|q| q > 0))
+}
+
+pub fn format_queue(queue: Option<u32>, highlight: bool) -> ColoredString {
+    match queue {
+        Some(q) if q > 0 && highlight => q.to_string().bold().yellow(),
+        _ => format_optional(queue).normal()
+    }
+}
+
+// Cut with an ellipsis when longer than the column
+pub fn format_cmdline(cmdline: Option<&str>, width: usize) -> String {
+    let cmdline = cmdline.unwrap_or("-");
+    if cmdline.chars().count() <= width {
+        return cmdline.to_string()
+    }
+    format!("{}...", cmdline.chars().take(width.saturating_sub(3)).collect::<String>())
+}
+
+// 'YYYY-MM-DD HH:MM:SS' in UTC
+pub fn format_start_time(start_time: Option<u64>) -> String {
+    let Some(start_time) = start_time else { return "-".to_string() };
+    let (days, seconds) = (start_time / 86400, start_time % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+pub fn format_optional<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or("-".to_string(), |v| v.to_string())
+}
+
+// RTTs are reported in microseconds
+pub fn format_rtt(rtt: Option<u32>) -> String {
+    rtt.map_or("-".to_string(), |v| format!("{:.2}ms", v as f64 / 1000.0))
+}
+
+// Same layout as the skmem:(...) output of 'ss -m'
+pub fn format_skmem(skmem: &Option<SocketMemory>) -> String {
+    match skmem {
+        Some(m) => format!("r{},rb{},t{},tb{},f{},w{},o{},bl{},d{}", m.rmem_alloc, m.rcvbuf, m.wmem_alloc, m.sndbuf, m.fwd_alloc, m.wmem_queued, m.optmem, m.backlog, m.drops),
+        None => "-".to_string()
+    }
+}
+
+pub fn map_protocol_color(protocol: &str, label: String) -> ColoredString {
+    match protocol {
+        "UDP" => label.bold().blue(),
+        "TCP" => label.bold().green(),
+        "UNIX" => label.bold().magenta(),
+        "RAW" => label.bold().black().on_yellow(),
+        "PACKET" => label.bold().white().on_red(),
+        _ => "unknown".bold().red()
+    }
+}
+
+// Raw and packet sockets see traffic that isn't addressed to them, so their owners are highlighted to make sniffers stand out
+pub fn map_process_color(protocol: &str, process_name: String) -> ColoredString {
+    match protocol {
+        "RAW" => process_name.bold().yellow(),
+        "PACKET" => process_name.bold().red(),
+        _ => process_name.bold()
+    }
+}
+
+pub fn map_state_color(state: &str) -> ColoredString{
+    match state {
         "CLOSED" => state.red(),
         "LISTEN" => state.cyan(),
         "SYN_SENT" => state.white(),
@@ -80,6 +263,9 @@ pub fn map_state_color(state: &String) -> ColoredString{
         "LAST_ACK" => state.white(),
         "TIME_WAIT" => state.white(),
         "DELETE_TCB" => state.white(),
+        "UNCONNECTED" => state.white(),
+        "CONNECTING" => state.white(),
+        "DISCONNECTING" => state.yellow(),
         "UNKNOWN" => state.purple(),
         _ => " ".white()
     }
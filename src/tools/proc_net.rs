@@ -0,0 +1,494 @@
+use std::{collections::HashMap, fs, net::{Ipv4Addr, Ipv6Addr}, path::Path, sync::LazyLock, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::tools::{connection_state_map::{map_packet_type, map_proc_tcp_state, map_unix_state, map_unix_type}, process_info::ProcessInfo, socket::Socket};
+
+pub const PROC_NET_DIR: &str = "/proc/net";
+
+pub struct ProcNetRow {
+    pub local_addr: String,
+    pub port: u16,
+    pub remote_addr: String,
+    pub remote_port: u16,
+    pub state: u8,
+    pub tx_queue: u32,
+    pub rx_queue: u32,
+    pub inode: u64
+}
+
+// Every open socket shows up in /proc/<pid>/fd as a link to 'socket:[<inode>]', the inode is what /proc/net/* tables report.
+// The link is timestamped when it is first looked up, which is only kept when it happened before this scan
+pub static SOCKET_INODE_MAP: LazyLock<HashMap<u64, (u32, Option<SystemTime>)>> = LazyLock::new(|| {
+    let mut map: HashMap<u64, (u32, Option<SystemTime>)> = HashMap::new();
+    let scan_start = SystemTime::now();
+    let Ok(proc_dir) = fs::read_dir("/proc") else { return map };
+    for entry in proc_dir.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else { continue };
+        let Ok(fd_dir) = fs::read_dir(entry.path().join("fd")) else { continue };
+        for fd in fd_dir.flatten() {
+            if let Some(inode) = fs::read_link(fd.path()).ok().and_then(|link| parse_socket_link(&link.to_string_lossy())) {
+                let linked_at = fs::symlink_metadata(fd.path()).and_then(|m| m.modified()).ok().filter(|t| *t < scan_start);
+                map.entry(inode).or_insert((pid, linked_at));
+            }
+        }
+    }
+    map
+});
+
+// Interface indexes can only be named inside our own namespace, the index itself is shown for any other one
+pub fn get_interface_name(index: u32, own_namespace: bool) -> Option<String> {
+    if index == 0 {
+        return None
+    }
+    if !own_namespace {
+        return Some(index.to_string())
+    }
+    let mut name = [0 as libc::c_char; libc::IF_NAMESIZE];
+    let result = unsafe { libc::if_indextoname(index, name.as_mut_ptr()) };
+    if result.is_null() {
+        return Some(index.to_string())
+    }
+    let name = unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) };
+    Some(name.to_string_lossy().to_string())
+}
+
+fn parse_socket_link(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+// Addresses are printed as the raw in-memory words of the address in host byte order
+fn parse_proc_net_address(address: &str) -> Option<(String, u16)> {
+    let (addr_hex, port_hex) = address.split_once(':')?;
+    let port = u16::from_str_radix(port_hex, 16).ok()?;
+    let mut bytes: Vec<u8> = Vec::with_capacity(16);
+    for i in (0..addr_hex.len()).step_by(8) {
+        let word = u32::from_str_radix(addr_hex.get(i..i + 8)?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    let addr = match bytes.len() {
+        4 => Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string(),
+        16 => Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string(),
+        _ => return None
+    };
+    Some((addr, port))
+}
+
+pub fn parse_proc_net_table(path: &Path) -> Vec<ProcNetRow> {
+    let Ok(content) = fs::read_to_string(path) else { return Vec::new() };
+    parse_proc_net_lines(&content)
+}
+
+fn parse_proc_net_lines(content: &str) -> Vec<ProcNetRow> {
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let (local_addr, port) = parse_proc_net_address(columns.get(1)?)?;
+            let (remote_addr, remote_port) = parse_proc_net_address(columns.get(2)?)?;
+            // 'tx_queue:rx_queue', for listeners the receive queue holds the connections waiting to be accepted
+            let (tx_queue, rx_queue) = columns.get(4)?.split_once(':')?;
+            Some(ProcNetRow {
+                local_addr,
+                port,
+                remote_addr,
+                remote_port,
+                state: u8::from_str_radix(columns.get(3)?, 16).ok()?,
+                tx_queue: u32::from_str_radix(tx_queue, 16).ok()?,
+                rx_queue: u32::from_str_radix(rx_queue, 16).ok()?,
+                inode: columns.get(9)?.parse().ok()?
+            })
+        })
+        .collect()
+}
+
+pub fn get_executable_path(pid: u32) -> Option<String> {
+    fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| path.to_string_lossy().trim_end_matches(" (deleted)").to_string())
+}
+
+// 'comm' is truncated to 15 characters so the executable name is preferred when it can be read
+pub fn get_process_name(pid: u32, executable_path: Option<&str>) -> String {
+    if let Some(name) = executable_path.and_then(|path| Path::new(path).file_name()) {
+        return name.to_string_lossy().to_string()
+    }
+    fs::read_to_string(format!("/proc/{}/comm", pid))
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+// Arguments are separated by NUL bytes, kernel threads have an empty command line
+fn get_cmdline(pid: u32) -> Option<String> {
+    let cmdline = fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+    let args: Vec<String> = cmdline.split(|&b| b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).to_string())
+        .collect();
+    if args.is_empty() { None } else { Some(args.join(" ")) }
+}
+
+// Goes through NSS like 'ps' does, so users from LDAP or systemd-homed resolve too. Unknown UIDs are shown as numbers
+fn get_user_name(uid: u32) -> String {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut result: *mut libc::passwd = std::ptr::null_mut();
+    let status = unsafe { libc::getpwuid_r(uid, &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut result) };
+    if status != 0 || result.is_null() {
+        return uid.to_string()
+    }
+    unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) }.to_string_lossy().to_string()
+}
+
+// Boot time in seconds since the epoch, process start times are counted in clock ticks from it
+static BOOT_TIME: LazyLock<Option<u64>> = LazyLock::new(|| {
+    fs::read_to_string("/proc/stat").ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("btime "))
+        .and_then(|btime| btime.trim().parse().ok())
+});
+
+// The process name in /proc/<pid>/stat may contain spaces and parentheses, so fields are counted from the last ')'. 'starttime' is field 22
+fn get_start_time(pid: u32) -> Option<u64> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let start_ticks: u64 = stat.rsplit_once(')')?.1.split_whitespace().nth(19)?.parse().ok()?;
+    let ticks_per_second = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+    if ticks_per_second <= 0 {
+        return None
+    }
+    Some((*BOOT_TIME)? + start_ticks / ticks_per_second as u64)
+}
+
+// The effective UID is used for the owning user, same as the USER column of 'ps'
+pub fn get_process_info(pid: u32) -> ProcessInfo {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid)).unwrap_or_default();
+    let status_field = |name: &str| status.lines().find_map(|line| line.strip_prefix(name)).map(str::trim);
+    ProcessInfo {
+        cmdline: get_cmdline(pid),
+        user: status_field("Uid:").and_then(|uids| uids.split_whitespace().nth(1)?.parse().ok()).map(get_user_name),
+        ppid: status_field("PPid:").and_then(|ppid| ppid.parse().ok()),
+        start_time: get_start_time(pid)
+    }
+}
+
+pub fn get_process_table() -> HashMap<u32, (u32, String)> {
+    let Ok(proc_dir) = fs::read_dir("/proc") else { return HashMap::new() };
+    proc_dir.flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let status = fs::read_to_string(entry.path().join("status")).ok()?;
+            let ppid = status.lines().find_map(|line| line.strip_prefix("PPid:"))?.trim().parse().ok()?;
+            Some((pid, (ppid, get_process_name(pid, get_executable_path(pid).as_deref()))))
+        })
+        .collect()
+}
+
+// Details about the owning processes, attached the same way by the procfs and netlink sources
+pub fn enrich_linux_sockets(sockets: &mut [Socket]) {
+    crate::tools::process_info::attach_process_info(sockets);
+    attach_uptime_estimates(sockets);
+    crate::tools::cgroup::attach_cgroup_info(sockets);
+    crate::tools::systemd_socket::attach_socket_units(sockets);
+}
+
+fn attach_uptime_estimates(sockets: &mut [Socket]) {
+    let now = SystemTime::now();
+    for socket in sockets.iter_mut().filter(|s| s.uptime == 0) {
+        let linked_at = socket.inode.and_then(|inode| SOCKET_INODE_MAP.get(&inode)).and_then(|(_, linked_at)| *linked_at);
+        let started_at = socket.start_time.map(|t| UNIX_EPOCH + Duration::from_secs(t));
+        let Some((created, bound)) = estimate_creation(linked_at, started_at) else { continue };
+        socket.uptime = now.duration_since(created).map_or(0, |d| d.as_secs());
+        socket.uptime_bound = Some(bound.to_string());
+    }
+}
+
+// Linux keeps no creation time for sockets, only two bounds: a socket is at least as old as its fd link when that link was
+// looked up before this run (by an earlier run or any other tool), and at most as old as its process. The link time is the
+// closer one whenever it is known, the process start time is only used without it
+fn estimate_creation(linked_at: Option<SystemTime>, started_at: Option<SystemTime>) -> Option<(SystemTime, &'static str)> {
+    match (linked_at, started_at) {
+        (Some(linked_at), _) => Some((linked_at, "lower")),
+        (None, Some(started_at)) => Some((started_at, "upper")),
+        (None, None) => None
+    }
+}
+
+// Sockets that can't be attributed (other users' sockets without root, TIME_WAIT) get PID 0
+pub fn get_inode_owner(inode: u64) -> (u32, String, Option<String>) {
+    match SOCKET_INODE_MAP.get(&inode) {
+        Some(&(pid, _)) => {
+            let executable_path = get_executable_path(pid);
+            (pid, get_process_name(pid, executable_path.as_deref()), executable_path)
+        }
+        None => (0, "unknown".to_string(), None)
+    }
+}
+
+fn get_proc_net_sockets(net_dir: &Path, table: &str, protocol: &'static str) -> Vec<Socket> {
+    let is_tcp = protocol == "TCP";
+    // TIME_WAIT sockets have no inode, they are kept and get PID 0 like other sockets without an owner
+    parse_proc_net_table(&net_dir.join(table))
+        .into_iter()
+        .map(|row| {
+            let (pid, process_name, executable_path) = get_inode_owner(row.inode);
+            Socket {
+                process_name,
+                pid,
+                port: row.port,
+                protocol,
+                local_addr: row.local_addr,
+                remote_addr: if is_tcp { Some(row.remote_addr) } else { None },
+                remote_port: if is_tcp { Some(row.remote_port) } else { None },
+                state: if is_tcp { map_proc_tcp_state(row.state) } else { " ".to_string() },
+                executable_path,
+                uptime: 0,
+                inode: Some(row.inode),
+                recv_q: Some(row.rx_queue),
+                send_q: Some(row.tx_queue),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+pub fn get_tcp_sockets(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_sockets(net_dir, "tcp", "TCP")
+}
+
+pub fn get_tcp_sockets_ipv6(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_sockets(net_dir, "tcp6", "TCP")
+}
+
+pub fn get_udp_sockets(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_sockets(net_dir, "udp", "UDP")
+}
+
+pub fn get_udp_sockets_ipv6(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_sockets(net_dir, "udp6", "UDP")
+}
+
+// Num RefCount Protocol Flags Type St Inode Path, the path is missing for unnamed sockets and starts with '@' for abstract ones
+pub fn get_unix_sockets(net_dir: &Path) -> Vec<Socket> {
+    let Ok(content) = fs::read_to_string(net_dir.join("unix")) else { return Vec::new() };
+    parse_unix_lines(&content)
+}
+
+fn parse_unix_lines(content: &str) -> Vec<Socket> {
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let flags = u32::from_str_radix(columns.get(3)?, 16).ok()?;
+            let socket_type = u16::from_str_radix(columns.get(4)?, 16).ok()?;
+            let state = u8::from_str_radix(columns.get(5)?, 16).ok()?;
+            let inode: u64 = columns.get(6)?.parse().ok()?;
+            let (pid, process_name, executable_path) = get_inode_owner(inode);
+            Some(Socket {
+                process_name,
+                pid,
+                port: 0,
+                protocol: "UNIX",
+                local_addr: columns.get(7).map(|path| path.to_string()).unwrap_or_default(),
+                remote_addr: None,
+                remote_port: None,
+                state: map_unix_state(flags, state),
+                executable_path,
+                uptime: 0,
+                inode: Some(inode),
+                socket_type: Some(map_unix_type(socket_type)),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+// Raw sockets use the same layout as the TCP/UDP tables with the IP protocol number in place of the local port
+fn get_proc_net_raw_sockets(net_dir: &Path, table: &str) -> Vec<Socket> {
+    parse_proc_net_table(&net_dir.join(table))
+        .into_iter()
+        .map(|row| {
+            let (pid, process_name, executable_path) = get_inode_owner(row.inode);
+            Socket {
+                process_name,
+                pid,
+                port: 0,
+                protocol: "RAW",
+                local_addr: row.local_addr,
+                remote_addr: None,
+                remote_port: None,
+                state: " ".to_string(),
+                executable_path,
+                uptime: 0,
+                inode: Some(row.inode),
+                recv_q: Some(row.rx_queue),
+                send_q: Some(row.tx_queue),
+                protocol_number: Some(row.port),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+pub fn get_raw_sockets(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_raw_sockets(net_dir, "raw")
+}
+
+pub fn get_raw_sockets_ipv6(net_dir: &Path) -> Vec<Socket> {
+    get_proc_net_raw_sockets(net_dir, "raw6")
+}
+
+// sk RefCnt Type Proto Iface R Rmem User Inode
+pub fn get_packet_sockets(net_dir: &Path) -> Vec<Socket> {
+    let Ok(content) = fs::read_to_string(net_dir.join("packet")) else { return Vec::new() };
+    parse_packet_lines(&content, net_dir == Path::new(PROC_NET_DIR))
+}
+
+fn parse_packet_lines(content: &str, own_namespace: bool) -> Vec<Socket> {
+    content.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let socket_type = columns.get(2)?.parse::<u16>().ok()?;
+            let ethertype = u16::from_str_radix(columns.get(3)?, 16).ok()?;
+            let interface_index = columns.get(4)?.parse::<u32>().ok()?;
+            let inode: u64 = columns.get(8)?.parse().ok()?;
+            let (pid, process_name, executable_path) = get_inode_owner(inode);
+            Some(Socket {
+                process_name,
+                pid,
+                port: 0,
+                protocol: "PACKET",
+                local_addr: String::new(),
+                remote_addr: None,
+                remote_port: None,
+                state: " ".to_string(),
+                executable_path,
+                uptime: 0,
+                inode: Some(inode),
+                socket_type: Some(map_packet_type(socket_type)),
+                protocol_number: Some(ethertype),
+                interface: get_interface_name(interface_index, own_namespace),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000003 00:00000000 00000000   999        0 23456 1 0000000000000000 100 0 0 10 0
+   1: 0F02000A:0016 0202000A:D431 01 00000024:00000000 02:0009F6A8 00000000     0        0 34567 4 0000000000000000 20 4 30 10 -1
+   2: 0F02000A:A3B2 22D8B85D:01BB 06 00000000:00000000 03:00001770 00000000     0        0 0 3 0000000000000000
+";
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 45678 1 0000000000000000 100 0 0 10 0
+";
+
+    #[test]
+    fn parses_ipv4_rows() {
+        let rows = parse_proc_net_lines(TCP);
+        assert_eq!(rows.len(), 3);
+        assert_eq!((rows[0].local_addr.as_str(), rows[0].port), ("127.0.0.1", 3306));
+        assert_eq!((rows[0].remote_addr.as_str(), rows[0].remote_port), ("0.0.0.0", 0));
+        assert_eq!(map_proc_tcp_state(rows[0].state), "LISTEN");
+        assert_eq!(rows[0].inode, 23456);
+        assert_eq!((rows[1].local_addr.as_str(), rows[1].port), ("10.0.2.15", 22));
+        assert_eq!((rows[1].remote_addr.as_str(), rows[1].remote_port), ("10.0.2.2", 54321));
+        assert_eq!(map_proc_tcp_state(rows[1].state), "ESTABLISHED");
+    }
+
+    #[test]
+    fn keeps_time_wait_rows_without_inode() {
+        let rows = parse_proc_net_lines(TCP);
+        assert_eq!((rows[2].remote_addr.as_str(), rows[2].remote_port), ("93.184.216.34", 443));
+        assert_eq!(map_proc_tcp_state(rows[2].state), "TIME_WAIT");
+        assert_eq!(rows[2].inode, 0);
+    }
+
+    #[test]
+    fn estimates_creation_from_link_time_or_process_start() {
+        let started_at = UNIX_EPOCH + Duration::from_secs(1717286400);
+        let linked_at = started_at + Duration::from_secs(3600);
+        assert_eq!(estimate_creation(Some(linked_at), Some(started_at)), Some((linked_at, "lower")));
+        assert_eq!(estimate_creation(None, Some(started_at)), Some((started_at, "upper")));
+        assert_eq!(estimate_creation(None, None), None);
+    }
+
+    #[test]
+    fn reads_queue_sizes() {
+        let rows = parse_proc_net_lines(TCP);
+        let queues: Vec<(u32, u32)> = rows.iter().map(|row| (row.rx_queue, row.tx_queue)).collect();
+        assert_eq!(queues, vec![(3, 0), (0, 36), (0, 0)]);
+    }
+
+    #[test]
+    fn parses_ipv6_rows() {
+        let rows = parse_proc_net_lines(TCP6);
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].local_addr.as_str(), rows[0].port), ("::1", 8080));
+        assert_eq!(rows[0].remote_addr, "::");
+    }
+
+    const UNIX: &str = "Num       RefCount Protocol Flags    Type St Inode Path
+0000000000000000: 00000002 00000000 00010000 0001 01 900000001 /run/docker.sock
+0000000000000000: 00000003 00000000 00000000 0001 03 900000002
+0000000000000000: 00000002 00000000 00000000 0002 01 900000003 /run/systemd/journal/dev-log
+0000000000000000: 00000002 00000000 00010000 0005 01 900000004 @/tmp/.X11-unix/X0
+";
+
+    #[test]
+    fn parses_unix_sockets() {
+        let sockets = parse_unix_lines(UNIX);
+        let rows: Vec<(&str, &str, Option<&str>, Option<u64>)> = sockets.iter()
+            .map(|s| (s.local_addr.as_str(), s.state.as_str(), s.socket_type.as_deref(), s.inode))
+            .collect();
+        assert_eq!(rows, vec![
+            ("/run/docker.sock", "LISTEN", Some("STREAM"), Some(900000001)),
+            ("", "ESTABLISHED", Some("STREAM"), Some(900000002)),
+            ("/run/systemd/journal/dev-log", "UNCONNECTED", Some("DGRAM"), Some(900000003)),
+            ("@/tmp/.X11-unix/X0", "LISTEN", Some("SEQPACKET"), Some(900000004))
+        ]);
+        assert!(sockets.iter().all(|s| s.protocol == "UNIX" && s.port == 0 && s.remote_addr.is_none()));
+    }
+
+    const RAW: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+   1: 00000000:0001 00000000:0000 07 00000000:00000000 00:00000000 00000000     0        0 9005 2 0000000000000000 0
+";
+
+    const PACKET: &str = "sk               RefCnt Type Proto  Iface R Rmem   User   Inode
+0000000000000000 3      3    0003   0     1 0      0      9006
+0000000000000000 3      2    88cc   0     1 0      0      9007
+";
+
+    #[test]
+    fn reads_protocol_number_of_raw_rows_from_local_port() {
+        let rows = parse_proc_net_lines(RAW);
+        assert_eq!(rows.len(), 1);
+        assert_eq!((rows[0].local_addr.as_str(), rows[0].port, rows[0].inode), ("0.0.0.0", 1, 9005));
+    }
+
+    #[test]
+    fn parses_packet_sockets() {
+        let sockets = parse_packet_lines(PACKET, true);
+        assert_eq!(sockets.len(), 2);
+        assert!(sockets.iter().all(|socket| socket.protocol == "PACKET" && socket.interface.is_none()));
+        assert_eq!((sockets[0].socket_type.as_deref(), sockets[0].protocol_number, sockets[0].inode), (Some("RAW"), Some(0x0003), Some(9006)));
+        assert_eq!((sockets[1].socket_type.as_deref(), sockets[1].protocol_number, sockets[1].inode), (Some("DGRAM"), Some(0x88cc), Some(9007)));
+    }
+
+    #[test]
+    fn leaves_interface_index_of_foreign_namespace_unnamed() {
+        let line = "0000000000000000 3      3    0003   1     1 0      0      9008";
+        let sockets = parse_packet_lines(&format!("{}{}\n", PACKET, line), false);
+        assert_eq!(sockets.iter().map(|socket| socket.interface.as_deref()).collect::<Vec<_>>(), vec![None, None, Some("1")]);
+    }
+
+    #[test]
+    fn parses_socket_links() {
+        assert_eq!(parse_socket_link("socket:[23456]"), Some(23456));
+        assert_eq!(parse_socket_link("pipe:[23456]"), None);
+        assert_eq!(parse_socket_link("/dev/null"), None);
+    }
+}
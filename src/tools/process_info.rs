@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use crate::tools::socket::Socket;
+
+#[derive(Clone, Default)]
+pub struct ProcessInfo {
+    pub cmdline: Option<String>,
+    pub user: Option<String>,
+    pub ppid: Option<u32>,
+    // Seconds since the epoch
+    pub start_time: Option<u64>
+}
+
+#[cfg(target_os = "linux")]
+fn get_process_info(pid: u32) -> ProcessInfo {
+    crate::tools::proc_net::get_process_info(pid)
+}
+
+#[cfg(windows)]
+fn get_process_info(pid: u32) -> ProcessInfo {
+    crate::tools::get_process_info::get_process_info(pid)
+}
+
+// Parent PID and name of every running process
+#[cfg(target_os = "linux")]
+pub fn get_process_table() -> HashMap<u32, (u32, String)> {
+    crate::tools::proc_net::get_process_table()
+}
+
+#[cfg(windows)]
+pub fn get_process_table() -> HashMap<u32, (u32, String)> {
+    crate::tools::get_process_info::PROCESS_TABLE.clone()
+}
+
+// Processes usually own several sockets, so each PID is only looked up once
+pub fn attach_process_info(sockets: &mut [Socket]) {
+    let mut process_infos: HashMap<u32, ProcessInfo> = HashMap::new();
+    for socket in sockets.iter_mut().filter(|s| s.pid != 0) {
+        let info = process_infos.entry(socket.pid).or_insert_with(|| get_process_info(socket.pid));
+        socket.cmdline = info.cmdline.clone();
+        socket.user = info.user.clone();
+        socket.ppid = info.ppid;
+        socket.start_time = info.start_time;
+    }
+}
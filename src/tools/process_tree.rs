@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use crate::tools::socket::Socket;
+
+pub struct ProcessTree {
+    // PID -> (parent PID, process name)
+    processes: HashMap<u32, (u32, String)>
+}
+
+impl ProcessTree {
+    // Parent PIDs come from the socket rows, the process table of the live system fills in the ancestors that own no socket
+    pub fn new(socket_table: &[Socket], live: bool) -> Self {
+        #[cfg(any(windows, target_os = "linux"))]
+        let mut processes = if live { crate::tools::process_info::get_process_table() } else { HashMap::new() };
+        #[cfg(not(any(windows, target_os = "linux")))]
+        let mut processes: HashMap<u32, (u32, String)> = { let _ = live; HashMap::new() };
+        for socket in socket_table {
+            if let Some(ppid) = socket.ppid {
+                processes.entry(socket.pid).or_insert_with(|| (ppid, socket.process_name.clone()));
+            }
+        }
+        ProcessTree { processes }
+    }
+
+    // PID 0 stands for no parent (init, kernel threads, the Windows System process)
+    fn get_parent(&self, pid: u32) -> Option<u32> {
+        self.processes.get(&pid).map(|(ppid, _)| *ppid).filter(|&ppid| ppid != 0 && ppid != pid)
+    }
+
+    pub fn get_name(&self, pid: u32) -> Option<&str> {
+        self.processes.get(&pid).map(|(_, name)| name.as_str())
+    }
+
+    // From the root down to the process itself. A parent missing from the table is kept as the root, and PID reuse loops are cut
+    pub fn get_ancestry(&self, pid: u32) -> Vec<u32> {
+        let mut ancestry = vec![pid];
+        while let Some(parent) = ancestry.last().and_then(|&p| self.get_parent(p)) {
+            if ancestry.contains(&parent) {
+                break
+            }
+            ancestry.push(parent);
+        }
+        ancestry.reverse();
+        ancestry
+    }
+
+    pub fn is_descendant(&self, pid: u32, ancestor: u32) -> bool {
+        self.get_ancestry(pid).contains(&ancestor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::socket_source::FixtureSource;
+
+    fn fixture_tree() -> ProcessTree {
+        ProcessTree::new(&FixtureSource::default().sockets, false)
+    }
+
+    #[test]
+    fn walks_ancestry_from_the_root() {
+        let tree = fixture_tree();
+        assert_eq!(tree.get_ancestry(1201), vec![1, 1200, 1201]);
+        assert_eq!(tree.get_ancestry(5100), vec![1, 900, 5100]);
+        assert_eq!(tree.get_name(1200), Some("nginx"));
+    }
+
+    #[test]
+    fn keeps_missing_parent_as_root() {
+        let tree = fixture_tree();
+        // The shell of python, ping and tcpdump owns no socket
+        assert_eq!(tree.get_ancestry(3100), vec![3050, 3100]);
+        assert_eq!(tree.get_name(3050), None);
+        // PID 0 stands for no parent
+        assert_eq!(tree.get_ancestry(4), vec![4]);
+    }
+
+    #[test]
+    fn finds_descendants() {
+        let tree = fixture_tree();
+        assert!(tree.is_descendant(1201, 1200));
+        assert!(tree.is_descendant(1201, 1));
+        assert!(tree.is_descendant(1200, 1200));
+        assert!(!tree.is_descendant(1200, 1201));
+        assert!(!tree.is_descendant(3100, 1));
+    }
+
+    #[test]
+    fn cuts_loops_of_reused_pids() {
+        let socket = |pid: u32, ppid: u32| Socket { pid, ppid: Some(ppid), process_name: format!("p{}", pid), ..Default::default() };
+        let tree = ProcessTree::new(&[socket(10, 11), socket(11, 10), socket(12, 12)], false);
+        assert_eq!(tree.get_ancestry(10), vec![11, 10]);
+        assert_eq!(tree.get_ancestry(12), vec![12]);
+    }
+}
@@ -1,96 +1,74 @@
-use std::{net::{Ipv4Addr, Ipv6Addr}, str::FromStr};
-use crate::tools::{rpderror::RpdError, socket::Socket, validate_address::validate_address};
+use std::{net::{IpAddr, Ipv4Addr, Ipv6Addr}, str::FromStr};
+use crate::tools::{rpderror::RpdError, socket::Socket, socket_filter::SocketPredicate, validate_address::validate_single_address};
 
 pub const MIN_IPV4: Ipv4Addr = Ipv4Addr::new(0,0,0,0);
 pub const MAX_IPV4: Ipv4Addr = Ipv4Addr::new(255,255,255,255);
 pub const MIN_IPV6: Ipv6Addr = Ipv6Addr::UNSPECIFIED;
 pub const MAX_IPV6: Ipv6Addr = Ipv6Addr::new(0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF);
 
-pub fn check_field_range<T: FromStr + Copy + PartialOrd>(default_min: T, default_max: T, range_args: &Vec<String>, socket_field: T) -> bool {
-    let min = range_args.get(1)
-        .map(|s| s.parse::<T>().unwrap_or(default_min))
-        .unwrap_or(default_min);
-    let max = range_args.get(2)
-        .map(|s| s.parse::<T>().unwrap_or(default_max))
-        .unwrap_or(default_max);
-    socket_field >= min && socket_field <= max
-}
-
-pub fn check_address_range<T: FromStr + Copy + PartialOrd>(default_min: T, default_max: T, range_args: &Vec<String>, socket_field: &String) -> bool {
-    let get_addr = T::from_str(socket_field.as_str());
-    match get_addr {
-        Ok(addr) => check_field_range(default_min, default_max, range_args, addr),
-        Err(_) => false
+// '-' leaves a bound open
+fn parse_range_bound<T: FromStr>(range_args: &[String], index: usize) -> Option<T> {
+    let bound = &range_args[index];
+    if bound == "-" {
+        return None
     }
-}
-
-pub fn filter_range(range_args: &Vec<String>, socket: &&Socket, ip_version: &Option<u8>) -> bool {
-    match range_args[0].to_lowercase().as_str() {
-        "pid" => check_field_range(u32::MIN, u32::MAX, range_args, socket.pid),
-        "port" => check_field_range(u16::MIN, u16::MAX, range_args, socket.port), 
-        "remote-port" => {
-            if let Some(remote_port) = socket.remote_port {
-                check_field_range(u16::MIN, u16::MAX, range_args, remote_port)
-            } else {
-                false
-            }
-        }
-        "uptime" => check_field_range(u64::MIN, u64::MAX, range_args, socket.uptime),
-        "local-address" => {
-            if let Some(version) = ip_version {
-                match version {
-                    4 => check_address_range(MIN_IPV4, MAX_IPV4, range_args, &socket.local_addr),
-                    6 => check_address_range(MIN_IPV6, MAX_IPV6, range_args, &socket.local_addr),
-                    _ => false
-                }
-            } else {
-                check_address_range(MIN_IPV4, MAX_IPV4, range_args, &socket.local_addr)
-            }
-        }
-        "remote-address" => {
-            if let Some(addr) = &socket.remote_addr {
-                if let Some(version) = ip_version {
-                    match version {
-                        4 => check_address_range(MIN_IPV4, MAX_IPV4, range_args, addr),
-                        6 => check_address_range(MIN_IPV6, MAX_IPV6, range_args, addr),
-                        _ => false
-                    }
-                } else {
-                    check_address_range(MIN_IPV4, MAX_IPV4, range_args, addr)
-                }
-            } else {
-                false
-            }
-        }
-        _ => {
-            false
-        }
+    match (bound.parse::<T>(), index) {
+        (Ok(value), _) => Some(value),
+        (Err(_), 1) => RpdError::InvalidRangeMinErr(bound.clone(), range_args[0].clone()).handle(),
+        (Err(_), _) => RpdError::InvalidRangeMaxErr(bound.clone(), range_args[0].clone()).handle()
     }
 }
 
-fn validate_range_fields<T: FromStr>((min, max): (Option<String>, Option<String>)) -> (bool, bool) {
-    let min_valid = min.clone()
-    .map(|f| f.parse::<T>().is_ok())
-    .unwrap_or(false);
-
-    let max_valid = max.clone()
-    .map(|f| f.parse::<T>().is_ok())
-    .unwrap_or(false);
+fn is_within<T: PartialOrd>(value: T, min: &Option<T>, max: &Option<T>) -> bool {
+    min.as_ref().is_none_or(|min| value >= *min) && max.as_ref().is_none_or(|max| value <= *max)
+}
 
-    (min_valid, max_valid)
+// Sockets without a value for the field never match the range
+fn field_range<T: FromStr + PartialOrd + 'static>(range_args: &[String], get_field: fn(&Socket) -> Option<T>) -> SocketPredicate {
+    let (min, max) = (parse_range_bound::<T>(range_args, 1), parse_range_bound::<T>(range_args, 2));
+    Box::new(move |socket| get_field(socket).is_some_and(|value| is_within(value, &min, &max)))
 }
 
-pub fn validate_range_args(range_args: &Vec<String>, ip_version: &Option<u8>) {
-    let range_fields_tuple = (range_args.get(1).cloned(), range_args.get(2).cloned());
-    let (min, max): (bool, bool) = match range_args[0].to_lowercase().as_str() {
-        "pid" => validate_range_fields::<u32>(range_fields_tuple),
-        "port" => validate_range_fields::<u16>(range_fields_tuple),
-        "remote-port" => validate_range_fields::<u16>(range_fields_tuple),
-        "uptime" => validate_range_fields::<u64>(range_fields_tuple),
-        "local-address" | "remote-address" => (validate_address(&range_args[1], ip_version), validate_address(&range_args[2], ip_version)),
-        _ => RpdError::InvalidRangeFieldErr(range_args[0].clone()).handle()
+// Bounds must be of the '--ip-version' family (IPv4 by default), addresses of the other family never match
+fn address_range(range_args: &[String], ip_version: &Option<u8>, get_field: fn(&Socket) -> Option<&str>) -> SocketPredicate {
+    let parse_bound = |index: usize| {
+        if range_args[index] != "-" && !validate_single_address(&range_args[index], ip_version) {
+            match index {
+                1 => RpdError::InvalidRangeMinErr(range_args[1].clone(), range_args[0].clone()).handle(),
+                _ => RpdError::InvalidRangeMaxErr(range_args[2].clone(), range_args[0].clone()).handle()
+            }
+        }
+        parse_range_bound::<IpAddr>(range_args, index)
     };
+    let (min, max) = (parse_bound(1), parse_bound(2));
+    // An open range still only takes addresses of the '--ip-version' family
+    let ipv4 = !matches!(ip_version, Some(6));
+    Box::new(move |socket| {
+        get_field(socket)
+            .and_then(|addr| IpAddr::from_str(addr).ok())
+            .is_some_and(|addr| addr.is_ipv4() == ipv4 && is_within(addr, &min, &max))
+    })
+}
 
-    if !min { RpdError::InvalidRangeMinErr(range_args[1].clone(), range_args[0].clone()).handle(); }
-    if !max { RpdError::InvalidRangeMaxErr(range_args[2].clone(), range_args[0].clone()).handle(); }
-}
\ No newline at end of file
+// Bounds are parsed and validated once, when the filters are built
+pub fn build_range_filter(range_args: &[String], ip_version: &Option<u8>) -> SocketPredicate {
+    match range_args[0].to_lowercase().as_str() {
+        "pid" => field_range::<u32>(range_args, |s| Some(s.pid)),
+        "port" => field_range::<u16>(range_args, |s| Some(s.port)),
+        "remote-port" => field_range::<u16>(range_args, |s| s.remote_port),
+        "uptime" => field_range::<u64>(range_args, |s| Some(s.uptime)),
+        "rtt" => field_range::<u32>(range_args, |s| s.rtt),
+        "rtt-var" => field_range::<u32>(range_args, |s| s.rtt_var),
+        "cwnd" => field_range::<u32>(range_args, |s| s.cwnd),
+        "retransmits" => field_range::<u32>(range_args, |s| s.retransmits),
+        "mss" => field_range::<u32>(range_args, |s| s.mss),
+        "skmem" => field_range::<u64>(range_args, |s| s.skmem.as_ref().map(|m| m.total())),
+        "orig-bytes" => field_range::<u64>(range_args, |s| s.orig_bytes),
+        "resp-bytes" => field_range::<u64>(range_args, |s| s.resp_bytes),
+        "recv-q" => field_range::<u32>(range_args, |s| s.recv_q),
+        "send-q" => field_range::<u32>(range_args, |s| s.send_q),
+        "local-address" => address_range(range_args, ip_version, |s| Some(s.local_addr.as_str())),
+        "remote-address" => address_range(range_args, ip_version, |s| s.remote_addr.as_deref()),
+        _ => RpdError::InvalidRangeFieldErr(range_args[0].clone()).handle()
+    }
+}
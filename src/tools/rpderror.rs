@@ -16,6 +16,17 @@ pub enum RpdError {
 
     InvalidProtocolErr(String),
 
+    InvalidSourceErr(String),
+
+    // Network namespace errors
+    InvalidNetnsErr(String),
+    NetnsUnsupportedErr(),
+
+    // '--input' errors
+    ReadInputErr(String, String),
+    ParseInputErr(String, String),
+    InvalidInputFormatErr(String),
+
     InvalidUptimeFormatErr(String),
 
     // Range filtering errors
@@ -23,6 +34,15 @@ pub enum RpdError {
     InvalidRangeMinErr(String, String),
     InvalidRangeMaxErr(String, String),
 
+    // '--where' errors: expression, byte offset and length of the offending token, message
+    InvalidWhereErr(String, usize, usize, String),
+
+    // Filter flag and value of a list filter ('--port', '--pid', ...)
+    InvalidValueListErr(String, String, String, String),
+
+    // Filter flag, pattern and regex error
+    InvalidRegexErr(String, String, String),
+
     // IP Address errors
     InvalidLocalAddressErr(String),
     InvalidRemoteAddressErr(String),
@@ -37,19 +57,35 @@ impl RpdError {
         match self {
             RpdError::ParseArgsErr(err) => eprintln!("{}", err),
             RpdError::RepeatedFieldArgErr(repeated_field) => eprintln!("error: Repeated field '{}'", repeated_field.bold().underline()),
-            RpdError::InvalidFieldArgErr(invalid_field) => eprintln!("error: Invalid field: '{}'\n\nAvailable fields:\n\n  - pid\n  - process_name\n  - port\n  - protocol\n  - local_address\n  - remote_address\n  - state\n  - uptime", invalid_field.bold().underline()),
+            RpdError::InvalidFieldArgErr(invalid_field) => eprintln!("error: Invalid field: '{}'\n\nAvailable fields:\n\n  - pid\n  - process-name\n  - port\n  - protocol\n  - local-address\n  - remote-address\n  - state\n  - uptime\n  - rtt\n  - rtt-var\n  - cwnd\n  - retransmits\n  - mss\n  - congestion\n  - skmem\n  - peer\n  - interface\n  - netns\n  - container\n  - unit\n  - orig-bytes\n  - resp-bytes\n  - cmdline\n  - user\n  - ppid\n  - start-time\n  - recv-q\n  - send-q", invalid_field.bold().underline()),
             RpdError::ConfigDirNotFoundErr() => eprintln!("error: Config directory not found"),
             RpdError::InvalidConfigKeyErr(invalid_key) => eprintln!("error: Invalid configuration key: '{}'\n\nUse '--help' to see available configurations or read the configuration file on 'AppData\\Roaming\\r-port-doctor\\config.toml'", invalid_key.bold().underline()),
             RpdError::UpdateConfigErr(err) => eprintln!("error: {}", err),
             RpdError::InvalidIpVersionErr() => eprintln!("error: Invalid IP version\n\nValid versions:\n\n  - 4 (IPv4)\n  - 6 (IPv6)"),
-            RpdError::InvalidProtocolErr(invalid_protocol) => eprintln!("error: Invalid protocol: '{}'\n\nAvailable protocols:\n\n  - TCP\n  - UDP", invalid_protocol.bold().underline()),
+            RpdError::InvalidProtocolErr(invalid_protocol) => eprintln!("error: Invalid protocol: '{}'\n\nAvailable protocols:\n\n  - TCP\n  - UDP\n  - UNIX (Linux)\n  - RAW (Linux)\n  - PACKET (Linux)", invalid_protocol.bold().underline()),
+            RpdError::InvalidSourceErr(invalid_source) => eprintln!("error: Invalid socket source: '{}'\n\nAvailable sources:\n\n  - iphelper (Windows)\n  - netlink (Linux)\n  - procfs (Linux)", invalid_source.bold().underline()),
+            RpdError::InvalidNetnsErr(invalid_netns) => eprintln!("error: No network namespace found for '{}'\n\nProvide either the inode of a network namespace or the PID of a process inside it", invalid_netns.bold().underline()),
+            RpdError::NetnsUnsupportedErr() => eprintln!("error: Network namespaces are only available on Linux"),
+            RpdError::ReadInputErr(input, err) => eprintln!("error: Could not read input '{}': {}", input.bold().underline(), err),
+            RpdError::ParseInputErr(input, err) => eprintln!("error: Could not parse socket table from input '{}': {}", input.bold().underline(), err),
+            RpdError::InvalidInputFormatErr(invalid_format) => eprintln!("error: Invalid input format: '{}'\n\nAvailable formats:\n\n  - json\n  - netstat\n  - ss\n  - lsof\n  - powershell\n  - pcap\n  - zeek", invalid_format.bold().underline()),
             RpdError::InvalidUptimeFormatErr(invalid_uptime_format) => eprintln!("error: Invalid uptime format: '{}'\n\nAvailable formats:\n\n  - clock\n  - human\n  - hours\n  - minutes\n  - seconds", invalid_uptime_format.bold().underline()),
             RpdError::InvalidRangeFieldErr(invalid_range_field) => eprintln!("error: Invalid <FIELD> value '{}' provided for range filtering", invalid_range_field.bold().underline()),
             RpdError::InvalidRangeMinErr(invalid_range_min, range_field) => eprintln!("error: Invalid <MIN> value '{}' provided for {} range filtering", invalid_range_min.bold().underline(), range_field.bold().underline()),
             RpdError::InvalidRangeMaxErr(invalid_range_max, range_field ) => eprintln!("error: Invalid <MAX> value '{}' provided for {} range filtering", invalid_range_max.bold().underline(), range_field.bold().underline()),
+            RpdError::InvalidWhereErr(expression, start, len, message) => {
+                let indent = expression[..*start].chars().count();
+                let width = expression.get(*start..start + len).map_or(1, |token| token.chars().count().max(1));
+                eprintln!("error: Invalid --where expression: {}\n\n  {}\n  {}{}", message, expression, " ".repeat(indent), "^".repeat(width).bold())
+            }
+            RpdError::InvalidValueListErr(flag, value, item, reason) => {
+                let list = if item != value { format!(" in '{}'", value) } else { String::new() };
+                eprintln!("error: Invalid value '{}'{} provided for {}: {}\n\nExpected a comma-separated list of values and 'min-max' ranges (e.g., 80,443,8000-8100)", item.bold().underline(), list, flag, reason)
+            }
+            RpdError::InvalidRegexErr(flag, pattern, err) => eprintln!("error: Invalid regular expression '{}' provided for {}\n\n{}", pattern.bold().underline(), flag, err),
             RpdError::InvalidLocalAddressErr(invalid_address) => eprintln!("error: Invalid local address provided: '{}'", invalid_address.bold().underline()),
             RpdError::InvalidRemoteAddressErr(invalid_address) => eprintln!("error: Invalid remote address provided: '{}'", invalid_address.bold().underline()),
-            RpdError::InvalidSortFieldErr(invalid_order_field) => eprintln!("error: Invalid sort field argument: '{}'\n\nAvailable arguments:\n\n  - pid (Process ID)\n  - port (Local Port)\n  - remote-port (Remote Port)\n  - process-name (Process Name)\n  - uptime (Time in seconds since connection started)", invalid_order_field.bold().underline()),
+            RpdError::InvalidSortFieldErr(invalid_order_field) => eprintln!("error: Invalid sort field argument: '{}'\n\nAvailable arguments:\n\n  - pid (Process ID)\n  - port (Local Port)\n  - remote-port (Remote Port)\n  - process-name (Process Name)\n  - uptime (Time in seconds since connection started)\n  - local-address\n  - remote-address\n  - rtt (Smoothed round-trip time)\n  - rtt-var (Round-trip time variance)\n  - cwnd (Congestion window)\n  - retransmits (Total retransmitted segments)\n  - mss (Maximum segment size)\n  - congestion (Congestion control algorithm)\n  - skmem (Allocated socket memory)\n  - netns (Network namespace inode)\n  - container (Container runtime and ID)\n  - unit (systemd unit)\n  - orig-bytes (Bytes sent by the originator)\n  - resp-bytes (Bytes sent by the responder)\n  - recv-q (Receive queue size)\n  - send-q (Send queue size)", invalid_order_field.bold().underline()),
             RpdError::InvalidSortOrderErr(invalid_order_arg) => eprintln!("error: Invalid sort order argument: '{}'\n\nAvailable orders:\n  - asc (ascendant)\n  - desc (descendant)", invalid_order_arg.bold().underline()),
         }
         std::process::exit(0);
@@ -0,0 +1,456 @@
+use std::{ffi::c_void, mem, net::{Ipv4Addr, Ipv6Addr}};
+
+use crate::tools::{connection_state_map::{map_proc_tcp_state, map_unix_diag_state, map_unix_type}, netns::{get_own_netns, get_thread_netns}, proc_net::{get_inode_owner, get_interface_name}, socket::{Socket, SocketMemory}};
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const NLMSG_HDRLEN: usize = 16;
+const RTA_HDRLEN: usize = 4;
+
+// linux/inet_diag.h
+const INET_DIAG_MSG_LEN: usize = 72;
+const INET_DIAG_INFO: u16 = 2;
+const INET_DIAG_CONG: u16 = 4;
+const INET_DIAG_SKMEMINFO: u16 = 7;
+const ALL_STATES: u32 = 0xFFFFFFFF;
+
+// linux/unix_diag.h
+const UNIX_DIAG_MSG_LEN: usize = 16;
+const UDIAG_SHOW_NAME: u32 = 0x01;
+const UDIAG_SHOW_PEER: u32 = 0x04;
+const UDIAG_SHOW_RQLEN: u32 = 0x10;
+const UNIX_DIAG_NAME: u16 = 0;
+const UNIX_DIAG_PEER: u16 = 2;
+const UNIX_DIAG_RQLEN: u16 = 4;
+
+// Offsets of the struct tcp_info fields read from the INET_DIAG_INFO attribute
+const TCPI_SND_MSS: usize = 16;
+const TCPI_RTT: usize = 68;
+const TCPI_RTTVAR: usize = 72;
+const TCPI_SND_CWND: usize = 80;
+const TCPI_TOTAL_RETRANS: usize = 100;
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+pub fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes.get(offset..offset + 2).map(|b| u16::from_ne_bytes([b[0], b[1]]))
+}
+
+pub fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes.get(offset..offset + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Splits the rtattr list that follows a diag message into (type, payload) pairs
+pub fn parse_attributes(bytes: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+    while let (Some(len), Some(attr_type)) = (read_u16(bytes, offset), read_u16(bytes, offset + 2)) {
+        let len = len as usize;
+        if len < RTA_HDRLEN || offset + len > bytes.len() {
+            break;
+        }
+        attributes.push((attr_type, &bytes[offset + RTA_HDRLEN..offset + len]));
+        offset += align(len);
+    }
+    attributes
+}
+
+fn build_request(payload: &[u8]) -> Vec<u8> {
+    let len = NLMSG_HDRLEN + payload.len();
+    let mut message: Vec<u8> = Vec::with_capacity(len);
+    message.extend_from_slice(&(len as u32).to_ne_bytes());
+    message.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    message.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    message.extend_from_slice(&1u32.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(payload);
+    message
+}
+
+// Sends a NETLINK_SOCK_DIAG dump request and returns the payload of every message of the reply
+pub fn sock_diag_dump(payload: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let fd = unsafe { libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG) };
+    if fd < 0 {
+        return None
+    }
+    let messages = sock_diag_exchange(fd, &build_request(payload));
+    unsafe { libc::close(fd); }
+    messages
+}
+
+fn sock_diag_exchange(fd: i32, request: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut kernel_addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+    kernel_addr.nl_family = libc::AF_NETLINK as u16;
+    let sent = unsafe {
+        libc::sendto(
+            fd,
+            request.as_ptr() as *const c_void,
+            request.len(),
+            0,
+            &kernel_addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            mem::size_of::<libc::sockaddr_nl>() as u32
+        )
+    };
+    if sent < 0 {
+        return None
+    }
+
+    let mut messages: Vec<Vec<u8>> = Vec::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let received = unsafe { libc::recv(fd, buffer.as_mut_ptr() as *mut c_void, buffer.len(), 0) };
+        if received <= 0 {
+            return None
+        }
+        let received = received as usize;
+        let mut offset = 0;
+        while let (Some(len), Some(message_type)) = (read_u32(&buffer[..received], offset), read_u16(&buffer[..received], offset + 4)) {
+            let len = len as usize;
+            if len < NLMSG_HDRLEN || offset + len > received {
+                break;
+            }
+            match message_type as i32 {
+                // Dumps that fail (e.g. the diag module for the protocol isn't available) carry a negative errno in NLMSG_DONE
+                libc::NLMSG_DONE => {
+                    let error = read_u32(&buffer[..received], offset + NLMSG_HDRLEN).map(|e| e as i32).unwrap_or(0);
+                    return if error < 0 { None } else { Some(messages) }
+                }
+                libc::NLMSG_ERROR => return None,
+                _ => messages.push(buffer[offset + NLMSG_HDRLEN..offset + len].to_vec())
+            }
+            offset += align(len);
+        }
+    }
+}
+
+fn parse_inet_address(bytes: &[u8], ipv6: bool) -> String {
+    if ipv6 {
+        let mut addr = [0u8; 16];
+        addr.copy_from_slice(&bytes[..16]);
+        Ipv6Addr::from(addr).to_string()
+    } else {
+        Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()
+    }
+}
+
+fn parse_tcp_info(socket: &mut Socket, tcp_info: &[u8]) {
+    socket.mss = read_u32(tcp_info, TCPI_SND_MSS);
+    socket.rtt = read_u32(tcp_info, TCPI_RTT);
+    socket.rtt_var = read_u32(tcp_info, TCPI_RTTVAR);
+    socket.cwnd = read_u32(tcp_info, TCPI_SND_CWND);
+    // Older kernels don't report the total, only the retransmits of the current RTO
+    socket.retransmits = read_u32(tcp_info, TCPI_TOTAL_RETRANS).or(tcp_info.get(2).map(|r| *r as u32));
+}
+
+fn parse_skmem(skmem: &[u8]) -> Option<SocketMemory> {
+    let value = |idx: usize| read_u32(skmem, idx * 4);
+    Some(SocketMemory {
+        rmem_alloc: value(0)?,
+        rcvbuf: value(1)?,
+        wmem_alloc: value(2)?,
+        sndbuf: value(3)?,
+        fwd_alloc: value(4)?,
+        wmem_queued: value(5)?,
+        optmem: value(6)?,
+        backlog: value(7)?,
+        drops: value(8).unwrap_or(0)
+    })
+}
+
+fn parse_inet_diag_msg(message: &[u8], protocol: &'static str, ipv6: bool, own_namespace: bool) -> Option<Socket> {
+    if message.len() < INET_DIAG_MSG_LEN {
+        return None
+    }
+    let is_tcp = protocol == "TCP";
+    let is_raw = protocol == "RAW";
+    // TIME_WAIT sockets have no inode, get_inode_owner then falls back to PID 0
+    let inode = read_u32(message, 68)? as u64;
+    let port = u16::from_be_bytes([message[4], message[5]]);
+
+    let (pid, process_name, executable_path) = get_inode_owner(inode);
+    let mut socket = Socket {
+        process_name,
+        pid,
+        port: if is_raw { 0 } else { port },
+        protocol,
+        local_addr: parse_inet_address(&message[8..24], ipv6),
+        remote_addr: if is_tcp { Some(parse_inet_address(&message[24..40], ipv6)) } else { None },
+        remote_port: if is_tcp { Some(u16::from_be_bytes([message[6], message[7]])) } else { None },
+        state: if is_tcp { map_proc_tcp_state(message[1]) } else { " ".to_string() },
+        executable_path,
+        uptime: 0,
+        inode: Some(inode),
+        // Raw sockets report their IP protocol number as the local port
+        protocol_number: if is_raw { Some(port) } else { None },
+        interface: read_u32(message, 40).and_then(|index| get_interface_name(index, own_namespace)),
+        // For listeners these are the accept queue and its maximum length, same as the Recv-Q and Send-Q columns of 'ss'
+        recv_q: read_u32(message, 56),
+        send_q: read_u32(message, 60),
+        ..Default::default()
+    };
+
+    for (attr_type, payload) in parse_attributes(&message[INET_DIAG_MSG_LEN..]) {
+        match attr_type {
+            INET_DIAG_INFO if is_tcp => parse_tcp_info(&mut socket, payload),
+            INET_DIAG_CONG => {
+                let name = payload.split(|b| *b == 0).next().unwrap_or_default();
+                socket.congestion = Some(String::from_utf8_lossy(name).to_string());
+            }
+            INET_DIAG_SKMEMINFO => socket.skmem = parse_skmem(payload),
+            _ => {}
+        }
+    }
+    Some(socket)
+}
+
+// Returns None when the kernel doesn't support sock_diag so the caller can fall back to procfs
+pub fn get_inet_diag_sockets(protocol: &'static str, ipv6: bool) -> Option<Vec<Socket>> {
+    let family = if ipv6 { libc::AF_INET6 } else { libc::AF_INET } as u8;
+    let ip_protocol = match protocol {
+        "TCP" => libc::IPPROTO_TCP,
+        "UDP" => libc::IPPROTO_UDP,
+        _ => libc::IPPROTO_RAW
+    } as u8;
+    let extensions: u8 = (1 << (INET_DIAG_INFO - 1)) | (1 << (INET_DIAG_CONG - 1)) | (1 << (INET_DIAG_SKMEMINFO - 1));
+
+    // struct inet_diag_req_v2, the zeroed inet_diag_sockid matches every socket
+    let mut request: Vec<u8> = vec![family, ip_protocol, extensions, 0];
+    request.extend_from_slice(&ALL_STATES.to_ne_bytes());
+    request.extend_from_slice(&[0u8; 48]);
+
+    let messages = sock_diag_dump(&request)?;
+    // The dump covers the namespace of the calling thread, its interface indexes can't be named from ours when they differ
+    let own_namespace = get_thread_netns() == get_own_netns();
+    Some(messages.iter().filter_map(|message| parse_inet_diag_msg(message, protocol, ipv6, own_namespace)).collect())
+}
+
+
+fn parse_unix_diag_msg(message: &[u8]) -> Option<Socket> {
+    if message.len() < UNIX_DIAG_MSG_LEN {
+        return None
+    }
+    let inode = read_u32(message, 4)? as u64;
+    let (pid, process_name, executable_path) = get_inode_owner(inode);
+    let mut socket = Socket {
+        process_name,
+        pid,
+        port: 0,
+        protocol: "UNIX",
+        local_addr: String::new(),
+        remote_addr: None,
+        remote_port: None,
+        state: map_unix_diag_state(message[2]),
+        executable_path,
+        uptime: 0,
+        inode: Some(inode),
+        socket_type: Some(map_unix_type(message[1] as u16)),
+        ..Default::default()
+    };
+
+    for (attr_type, payload) in parse_attributes(&message[UNIX_DIAG_MSG_LEN..]) {
+        match attr_type {
+            // Abstract socket names start with a NUL byte, shown with a leading '@' like /proc/net/unix does
+            UNIX_DIAG_NAME => {
+                socket.local_addr = match payload.split_first() {
+                    Some((0, name)) => format!("@{}", String::from_utf8_lossy(name)),
+                    _ => String::from_utf8_lossy(payload.split(|b| *b == 0).next().unwrap_or_default()).to_string()
+                };
+            }
+            UNIX_DIAG_PEER => {
+                if let Some(peer_inode) = read_u32(payload, 0).filter(|peer| *peer != 0) {
+                    let (peer_pid, peer_process, _) = get_inode_owner(peer_inode as u64);
+                    socket.peer_inode = Some(peer_inode as u64);
+                    socket.peer_pid = Some(peer_pid);
+                    socket.peer_process = Some(peer_process);
+                }
+            }
+            UNIX_DIAG_RQLEN => {
+                socket.recv_q = read_u32(payload, 0);
+                socket.send_q = read_u32(payload, 4);
+            }
+            _ => {}
+        }
+    }
+    Some(socket)
+}
+
+pub fn get_unix_diag_sockets() -> Option<Vec<Socket>> {
+    // struct unix_diag_req
+    let mut request: Vec<u8> = vec![libc::AF_UNIX as u8, 0, 0, 0];
+    request.extend_from_slice(&ALL_STATES.to_ne_bytes());
+    request.extend_from_slice(&0u32.to_ne_bytes());
+    request.extend_from_slice(&(UDIAG_SHOW_NAME | UDIAG_SHOW_PEER | UDIAG_SHOW_RQLEN).to_ne_bytes());
+    request.extend_from_slice(&[0u8; 8]);
+
+    let messages = sock_diag_dump(&request)?;
+    Some(messages.iter().filter_map(|message| parse_unix_diag_msg(message)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // struct inet_diag_msg of an IPv4 socket
+    fn inet_diag_msg(state: u8, local: ([u8; 4], u16), remote: ([u8; 4], u16), inode: u32) -> Vec<u8> {
+        let mut message = vec![0u8; INET_DIAG_MSG_LEN];
+        message[0] = libc::AF_INET as u8;
+        message[1] = state;
+        message[4..6].copy_from_slice(&local.1.to_be_bytes());
+        message[6..8].copy_from_slice(&remote.1.to_be_bytes());
+        message[8..12].copy_from_slice(&local.0);
+        message[24..28].copy_from_slice(&remote.0);
+        message[68..72].copy_from_slice(&inode.to_ne_bytes());
+        message
+    }
+
+    fn attribute(attr_type: u16, payload: &[u8]) -> Vec<u8> {
+        let mut attribute = ((RTA_HDRLEN + payload.len()) as u16).to_ne_bytes().to_vec();
+        attribute.extend_from_slice(&attr_type.to_ne_bytes());
+        attribute.extend_from_slice(payload);
+        attribute.resize(align(attribute.len()), 0);
+        attribute
+    }
+
+    fn u32_fields(len: usize, fields: &[(usize, u32)]) -> Vec<u8> {
+        let mut bytes = vec![0u8; len];
+        for (offset, value) in fields {
+            bytes[*offset..*offset + 4].copy_from_slice(&value.to_ne_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn splits_aligned_attributes() {
+        let mut bytes = attribute(INET_DIAG_CONG, b"bbr\0");
+        bytes.extend(attribute(INET_DIAG_SKMEMINFO, &[1, 2, 3, 4]));
+        // Truncated attribute claiming more bytes than left
+        bytes.extend_from_slice(&[64, 0, 2, 0]);
+        assert_eq!(parse_attributes(&bytes), vec![(INET_DIAG_CONG, &b"bbr\0"[..]), (INET_DIAG_SKMEMINFO, &[1u8, 2, 3, 4][..])]);
+    }
+
+    #[test]
+    fn parses_tcp_socket_with_internals() {
+        let mut message = inet_diag_msg(0x01, ([10, 0, 0, 5], 443), ([203, 0, 113, 10], 51234), 0);
+        let tcp_info = u32_fields(104, &[(TCPI_SND_MSS, 1448), (TCPI_RTT, 2500), (TCPI_RTTVAR, 1250), (TCPI_SND_CWND, 10), (TCPI_TOTAL_RETRANS, 3)]);
+        message.extend(attribute(INET_DIAG_INFO, &tcp_info));
+        message.extend(attribute(INET_DIAG_CONG, b"cubic\0"));
+        message.extend(attribute(INET_DIAG_SKMEMINFO, &u32_fields(36, &[(0, 512), (4, 131072), (8, 256), (12, 87040), (20, 1024), (32, 2)])));
+
+        let socket = parse_inet_diag_msg(&message, "TCP", false, true).unwrap();
+        assert_eq!((socket.local_addr.as_str(), socket.port), ("10.0.0.5", 443));
+        assert_eq!((socket.remote_addr.as_deref(), socket.remote_port), (Some("203.0.113.10"), Some(51234)));
+        assert_eq!(socket.state, "ESTABLISHED");
+        assert_eq!((socket.mss, socket.rtt, socket.rtt_var, socket.cwnd, socket.retransmits), (Some(1448), Some(2500), Some(1250), Some(10), Some(3)));
+        assert_eq!(socket.congestion.as_deref(), Some("cubic"));
+        let skmem = socket.skmem.unwrap();
+        assert_eq!((skmem.rcvbuf, skmem.sndbuf, skmem.drops), (131072, 87040, 2));
+        assert_eq!(skmem.total(), 512 + 256 + 1024);
+    }
+
+    #[test]
+    fn reads_accept_queue_of_listeners() {
+        let mut message = inet_diag_msg(0x0A, ([127, 0, 0, 1], 5432), ([0, 0, 0, 0], 0), 9002);
+        message[56..60].copy_from_slice(&3u32.to_ne_bytes());
+        message[60..64].copy_from_slice(&244u32.to_ne_bytes());
+        let socket = parse_inet_diag_msg(&message, "TCP", false, true).unwrap();
+        assert_eq!((socket.state.as_str(), socket.recv_q, socket.send_q), ("LISTEN", Some(3), Some(244)));
+    }
+
+    #[test]
+    fn falls_back_to_current_retransmits_on_older_kernels() {
+        let mut tcp_info = u32_fields(96, &[(TCPI_RTT, 800)]);
+        tcp_info[2] = 4;
+        let mut socket = Socket::default();
+        parse_tcp_info(&mut socket, &tcp_info);
+        assert_eq!((socket.rtt, socket.retransmits), (Some(800), Some(4)));
+    }
+
+    #[test]
+    fn keeps_time_wait_sockets_without_inode() {
+        let message = inet_diag_msg(0x06, ([10, 0, 0, 5], 49830), ([93, 184, 216, 34], 443), 0);
+        let socket = parse_inet_diag_msg(&message, "TCP", false, true).unwrap();
+        assert_eq!(socket.state, "TIME_WAIT");
+        assert_eq!((socket.pid, socket.process_name.as_str()), (0, "unknown"));
+    }
+
+    #[test]
+    fn ignores_tcp_info_and_remote_end_of_udp_sockets() {
+        let mut message = inet_diag_msg(0x07, ([127, 0, 0, 53], 53), ([0, 0, 0, 0], 0), 0);
+        message.extend(attribute(INET_DIAG_INFO, &u32_fields(104, &[(TCPI_RTT, 2500)])));
+        let socket = parse_inet_diag_msg(&message, "UDP", false, true).unwrap();
+        assert_eq!((socket.local_addr.as_str(), socket.port), ("127.0.0.53", 53));
+        assert_eq!((socket.remote_addr, socket.remote_port, socket.rtt), (None, None, None));
+        assert_eq!(socket.state, " ");
+    }
+
+    #[test]
+    fn reads_protocol_number_of_raw_sockets_from_local_port() {
+        let message = inet_diag_msg(0x07, ([0, 0, 0, 0], 1), ([0, 0, 0, 0], 0), 9005);
+        let socket = parse_inet_diag_msg(&message, "RAW", false, true).unwrap();
+        assert_eq!((socket.port, socket.protocol_number), (0, Some(1)));
+        assert_eq!((socket.remote_addr, socket.remote_port), (None, None));
+    }
+
+    #[test]
+    fn leaves_interface_index_of_foreign_namespace_unnamed() {
+        let mut message = inet_diag_msg(0x07, ([0, 0, 0, 0], 1), ([0, 0, 0, 0], 0), 9005);
+        message[40..44].copy_from_slice(&1u32.to_ne_bytes());
+        let socket = parse_inet_diag_msg(&message, "RAW", false, false).unwrap();
+        assert_eq!(socket.interface.as_deref(), Some("1"));
+        message[40..44].copy_from_slice(&0u32.to_ne_bytes());
+        assert_eq!(parse_inet_diag_msg(&message, "RAW", false, false).unwrap().interface, None);
+    }
+
+    #[test]
+    fn rejects_short_messages() {
+        assert!(parse_inet_diag_msg(&[0u8; 40], "TCP", false, true).is_none());
+        assert!(parse_unix_diag_msg(&[0u8; 8]).is_none());
+    }
+
+    // struct unix_diag_msg
+    fn unix_diag_msg(socket_type: u8, state: u8, inode: u32) -> Vec<u8> {
+        let mut message = vec![libc::AF_UNIX as u8, socket_type, state, 0];
+        message.extend_from_slice(&inode.to_ne_bytes());
+        message.extend_from_slice(&[0u8; 8]);
+        message
+    }
+
+    #[test]
+    fn parses_unix_socket_names_and_peers() {
+        let mut message = unix_diag_msg(1, 0x01, 900000002);
+        message.extend(attribute(UNIX_DIAG_NAME, b"/run/docker.sock"));
+        message.extend(attribute(UNIX_DIAG_PEER, &900000001u32.to_ne_bytes()));
+        let socket = parse_unix_diag_msg(&message).unwrap();
+        assert_eq!((socket.local_addr.as_str(), socket.state.as_str(), socket.socket_type.as_deref()), ("/run/docker.sock", "ESTABLISHED", Some("STREAM")));
+        assert_eq!(socket.inode, Some(900000002));
+        // No process owns the peer inode here, it is still recorded
+        assert_eq!((socket.peer_inode, socket.peer_pid, socket.peer_process.as_deref()), (Some(900000001), Some(0), Some("unknown")));
+    }
+
+    #[test]
+    fn shows_abstract_names_with_a_leading_at() {
+        let mut message = unix_diag_msg(2, 0x07, 900000003);
+        message.extend(attribute(UNIX_DIAG_NAME, b"\0/tmp/.X11-unix/X0"));
+        let socket = parse_unix_diag_msg(&message).unwrap();
+        assert_eq!(socket.local_addr, "@/tmp/.X11-unix/X0");
+        assert_eq!((socket.state.as_str(), socket.socket_type.as_deref()), ("UNCONNECTED", Some("DGRAM")));
+    }
+
+    #[test]
+    fn leaves_unconnected_unix_sockets_without_peer() {
+        let mut message = unix_diag_msg(1, 0x0A, 900000004);
+        message.extend(attribute(UNIX_DIAG_PEER, &0u32.to_ne_bytes()));
+        let socket = parse_unix_diag_msg(&message).unwrap();
+        assert_eq!(socket.state, "LISTEN");
+        assert_eq!((socket.local_addr.as_str(), socket.peer_inode, socket.peer_process), ("", None, None));
+    }
+
+    #[test]
+    fn reads_unix_queue_lengths() {
+        let mut message = unix_diag_msg(1, 0x0A, 900000001);
+        message.extend(attribute(UNIX_DIAG_RQLEN, &u32_fields(8, &[(0, 2), (4, 4096)])));
+        let socket = parse_unix_diag_msg(&message).unwrap();
+        assert_eq!((socket.recv_q, socket.send_q), (Some(2), Some(4096)));
+    }
+}
@@ -1,28 +1,158 @@
 use std::{net::{IpAddr, Ipv4Addr, Ipv6Addr}, str::FromStr};
-use regex::Regex;
+#[cfg(windows)]
 use windows::Win32::Networking::WinSock::{AF_INET, AF_INET6};
-use crate::tools::{args::Args, print::*, print_utils::*, range_filter::{filter_range, validate_range_args, MIN_IPV4, MIN_IPV6}, rpderror::RpdError, validate_address::validate_address};
-use serde::Serialize;
+use crate::tools::{args::Args, print::*, print_utils::*, process_tree::ProcessTree, range_filter::{MIN_IPV4, MIN_IPV6}, rpderror::RpdError, socket_filter::build_socket_filters};
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+// Aliased so serde does not treat the field as borrowed from the input, which would restrict deserializing to 'static data
+pub type Protocol = &'static str;
 
 //--------------------------------------------------------------------------------------------------------------------------
-#[derive(Clone, Serialize)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct Socket {
     pub process_name: String,
     pub pid: u32,
     pub port: u16,
-    pub protocol: &'static str,
+    #[serde(deserialize_with = "deserialize_protocol")]
+    pub protocol: Protocol,
     pub local_addr: String,
     pub remote_addr: Option<String>,
     pub remote_port: Option<u16>,
     pub state: String,
     pub executable_path: Option<String>,
-    pub uptime: u64
+    pub uptime: u64,
+    // Set when the uptime was estimated instead of read from a socket creation timestamp (Linux): "lower" when it was derived
+    // from the fd link, "upper" when it was derived from the process start time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uptime_bound: Option<String>,
+    // Owning process details, collected once per PID by the live collectors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ppid: Option<u32>,
+    // Seconds since the epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inode: Option<u64>,
+    // STREAM, DGRAM or SEQPACKET for Unix domain sockets, RAW or DGRAM for packet sockets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_type: Option<String>,
+    // IP protocol number for raw sockets, ethertype for packet sockets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol_number: Option<u16>,
+    // Interface the socket is bound to, None when bound to every interface
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interface: Option<String>,
+    // Inode of the network namespace the socket was found in, only set with '--all-netns' or '--netns'
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub netns: Option<u64>,
+    // Container of the owning process, parsed from its cgroup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<Container>,
+    // systemd unit and slice of the owning process, parsed from its cgroup
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slice: Option<String>,
+    // Service started by the '.socket' unit holding the socket, only set for listeners created by systemd socket activation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub activates: Option<String>,
+    // Counterpart of a connected Unix socket, only available from the netlink collector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_inode: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_process: Option<String>,
+
+    // TCP internals, only available from the netlink collector
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtt_var: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwnd: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retransmits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mss: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub congestion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skmem: Option<SocketMemory>,
+
+    // Bytes waiting in the receive and send queues, connections waiting to be accepted for listeners
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recv_q: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub send_q: Option<u32>,
+
+    // Payload bytes sent by the originator and the responder of the connection, only available from imported Zeek logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orig_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resp_bytes: Option<u64>
+}
+
+// Mirrors the SK_MEMINFO_* counters reported by the kernel (values in bytes, except for drops)
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SocketMemory {
+    pub rmem_alloc: u32,
+    pub rcvbuf: u32,
+    pub wmem_alloc: u32,
+    pub sndbuf: u32,
+    pub fwd_alloc: u32,
+    pub wmem_queued: u32,
+    pub optmem: u32,
+    pub backlog: u32,
+    pub drops: u32
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Container {
+    pub id: String,
+    // docker, podman, containerd, cri-o, kubernetes or lxc
+    pub runtime: String,
+    // Only set for Kubernetes pods
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod_uid: Option<String>
+}
+
+impl Container {
+    // Same 12 character short ID printed by 'docker ps'
+    pub fn label(&self) -> String {
+        format!("{}:{}", self.runtime, self.id.get(..12).unwrap_or(&self.id))
+    }
+}
+
+impl SocketMemory {
+    // Memory currently allocated by the socket, used as the value of the 'skmem' field for sorting and range filtering
+    pub fn total(&self) -> u64 {
+        self.rmem_alloc as u64 + self.wmem_alloc as u64 + self.wmem_queued as u64
+    }
 }
 
+#[cfg(windows)]
 pub const IPV4_ULAF: u32 = AF_INET.0 as u32;
+#[cfg(windows)]
 pub const IPV6_ULAF: u32 = AF_INET6.0 as u32;
 
-const TABLE_COLUMNS: usize = 8;
+pub const PROTOCOLS: [&str; 5] = ["TCP", "UDP", "UNIX", "RAW", "PACKET"];
+
+pub fn map_protocol(protocol: &str) -> Option<&'static str> {
+    PROTOCOLS.iter().find(|p| p.eq_ignore_ascii_case(protocol)).copied()
+}
+
+// Protocol names are kept as static strings, so the deserialized value is mapped back into one of them
+fn deserialize_protocol<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Protocol, D::Error> {
+    let protocol = String::deserialize(deserializer)?;
+    map_protocol(&protocol).ok_or_else(|| D::Error::custom(format!("unknown protocol '{}'", protocol)))
+}
+
+const TABLE_COLUMNS: usize = OUTPUT_FIELDS.len();
 const PID_W: usize = 10;
 const PORT_W: usize = 14;
 const PROTOCOL_W: usize = 10;
@@ -31,112 +161,41 @@ const UPTIME_W: usize = 12;
 const PROCESS_W: usize = 14;
 const LOCAL_ADDR_W: usize = 15;
 const REMOTE_ADDR_W: usize = 16;
+const RTT_W: usize = 10;
+const CWND_W: usize = 8;
+const RETRANSMITS_W: usize = 13;
+const MSS_W: usize = 8;
+const CONGESTION_W: usize = 12;
+const SKMEM_W: usize = 15;
+const PEER_W: usize = 10;
+const INTERFACE_W: usize = 11;
+const NETNS_W: usize = 12;
+const CONTAINER_W: usize = 11;
+const UNIT_W: usize = 8;
+const BYTES_W: usize = 12;
+const CMDLINE_W: usize = 14;
+// Long command lines (e.g. Java classpaths) are cut to keep the table readable, JSON output keeps them whole
+const CMDLINE_MAX_W: usize = 80;
+const USER_W: usize = 8;
+const PPID_W: usize = 10;
+const START_TIME_W: usize = 21;
+const QUEUE_W: usize = 8;
 
 impl Socket {
-    pub fn filter_socket_row (args: &Args, socket: &&Socket) -> bool {
-        if let Some(range_args) = &args.range {
-            if !filter_range(range_args, socket, &args.ip_version) {
-                return false
-            }
-        } 
-
-        if let Some(p) = args.port {
-            if socket.port != p {
-                return false
-            }
-        }
-
-        if let Some(p) = args.remote_port {
-            if let Some(rp) = socket.remote_port {
-                if rp != p {
-                    return false
-                }
-            } else {
-                return false
-            }       
-        }
-
-        if let Some(n) = &args.process_name {
-            match Regex::new(&n) {
-                Ok(re) => {
-                    if !re.is_match(&socket.process_name) {
-                        return false
-                    }
-                }
-                Err(_) => {
-                    return false
-                }
-            }
-        }
-
-        if let Some(i) = args.pid {
-            if socket.pid != i {
-                return false
-            }
-        }
-
-        if let Some(s) = &args.state {
-            if socket.state.to_string().to_lowercase() != s.to_lowercase() {
-                return false
-            }
-        }
-
-        if let Some(l) = &args.local_address {
-            if socket.local_addr.to_string().to_lowercase() != l.to_lowercase() {
-                return false
-            }
-        }
-
-        if let Some(r) = &args.remote_address {
-            if let Some(remote_addr) = &socket.remote_addr {
-                if remote_addr.to_string().to_lowercase() != r.to_lowercase() {
-                    return false
-                }
-            } else {
-                return false
-            }
-        }
-
-        if let Some(s) = args.older_than {
-            if socket.uptime < s as u64 {
-                return false
-            }
-        }
-
-        if let Some(s) = args.younger_than {
-            if socket.uptime > s as u64 {
-                return false
-            }
-        }
-
-        if args.no_system {
-            if socket.pid == 4 {
-                return false
-            }
-        }
-
-        true
-    }
-
     pub fn filter_socket_table(socket_table: &mut Vec<Socket>, args: &Args, argc: usize) {
         if argc > 0 {
-            if matches!(&args.local_address, Some(addr) if !validate_address(addr, &args.ip_version)) {
-                RpdError::InvalidLocalAddressErr(args.local_address.clone().unwrap()).handle();
-            }
-
-            if matches!(&args.remote_address, Some(addr) if !validate_address(addr, &args.ip_version)) {
-                RpdError::InvalidRemoteAddressErr(args.remote_address.clone().unwrap()).handle();
-            }
+            let filters = build_socket_filters(args);
+            socket_table.retain(|s| filters.iter().all(|filter| filter(s)));
 
-            if let Some(range_args) = &args.range {
-                validate_range_args(range_args, &args.ip_version);
+            // Descendants are found through the whole table, so the tree is built once the other filters are applied
+            if let Some(root) = args.pid_tree {
+                let tree = ProcessTree::new(socket_table, args.reads_live_system());
+                socket_table.retain(|s| tree.is_descendant(s.pid, root));
             }
-
-            *socket_table = socket_table.iter().filter(|s| Socket::filter_socket_row(&args, s)).cloned().collect();
         }
     }
 
-    pub fn sort_socket_table(socket_table: &mut Vec<Socket>, args: &Args) {
+    pub fn sort_socket_table(socket_table: &mut [Socket], args: &Args) {
         if let Some(sort_arg) = args.sort_by.clone() {
             let order = sort_arg[0].to_lowercase();
             let field = sort_arg[1].to_lowercase();
@@ -146,6 +205,20 @@ impl Socket {
                 "remote-port" => sort_by(order.as_str(), socket_table, |s| s.remote_port),
                 "process-name" => sort_by(order.as_str(), socket_table, |s| s.process_name.clone()),
                 "uptime" => sort_by(order.as_str(), socket_table, |s| s.uptime),
+                "rtt" => sort_by(order.as_str(), socket_table, |s| s.rtt),
+                "rtt-var" => sort_by(order.as_str(), socket_table, |s| s.rtt_var),
+                "cwnd" => sort_by(order.as_str(), socket_table, |s| s.cwnd),
+                "retransmits" => sort_by(order.as_str(), socket_table, |s| s.retransmits),
+                "mss" => sort_by(order.as_str(), socket_table, |s| s.mss),
+                "congestion" => sort_by(order.as_str(), socket_table, |s| s.congestion.clone()),
+                "skmem" => sort_by(order.as_str(), socket_table, |s| s.skmem.as_ref().map(|m| m.total())),
+                "netns" => sort_by(order.as_str(), socket_table, |s| s.netns),
+                "container" => sort_by(order.as_str(), socket_table, |s| s.container.as_ref().map(|c| c.label())),
+                "unit" => sort_by(order.as_str(), socket_table, |s| s.unit.clone()),
+                "orig-bytes" => sort_by(order.as_str(), socket_table, |s| s.orig_bytes),
+                "resp-bytes" => sort_by(order.as_str(), socket_table, |s| s.resp_bytes),
+                "recv-q" => sort_by(order.as_str(), socket_table, |s| s.recv_q),
+                "send-q" => sort_by(order.as_str(), socket_table, |s| s.send_q),
                 // Ipv4Addr and Ipv6Addr are mapped into IpAddr so the case returns the same type no matter the IP version
                 "local-address" => sort_by(order.as_str(), socket_table, |s| {
                     if matches!(args.ip_version, Some(version) if version == 6) {
@@ -188,26 +261,80 @@ impl Socket {
         let mut largest_local_addr: usize = 0;
         let mut largest_remote_addr: usize = 0;
         let mut largest_uptime: usize = 0;
+        let mut largest_protocol: usize = 0;
+        let mut largest_unix_path: usize = 0;
+        let mut largest_congestion: usize = 0;
+        let mut largest_skmem: usize = 0;
+        let mut largest_peer: usize = 0;
+        let mut largest_port: usize = 0;
+        let mut largest_interface: usize = 0;
+        let mut largest_container: usize = 0;
+        let mut largest_unit: usize = 0;
+        let mut largest_cmdline: usize = 0;
+        let mut largest_user: usize = 0;
 
         socket_table.iter().for_each(|socket| {
             largest_file_name = largest_file_name.max(socket.process_name.len());
-            largest_local_addr = largest_local_addr.max(socket.local_addr.len());
+            largest_protocol = largest_protocol.max(get_protocol_label(socket).len());
+            if spans_address_columns(socket) {
+                largest_unix_path = largest_unix_path.max(get_local_address_label(socket).len());
+            } else {
+                largest_local_addr = largest_local_addr.max(socket.local_addr.len());
+            }
             largest_uptime = largest_uptime.max(get_formatted_uptime(&args.uptime_format, socket.uptime).len());
             if let Some(addr) = &socket.remote_addr {
                 largest_remote_addr = largest_remote_addr.max(addr.len());
             }
+            if let Some(congestion) = &socket.congestion {
+                largest_congestion = largest_congestion.max(congestion.len());
+            }
+            largest_skmem = largest_skmem.max(format_skmem(&socket.skmem).len());
+            largest_peer = largest_peer.max(format_peer(socket).len());
+            largest_port = largest_port.max(format_port(socket).len());
+            largest_interface = largest_interface.max(format_interface(socket.interface.as_deref()).len());
+            largest_container = largest_container.max(format_container(socket).len());
+            largest_unit = largest_unit.max(format_optional(socket.unit.as_deref()).len());
+            largest_cmdline = largest_cmdline.max(format_optional(socket.cmdline.as_deref()).len());
+            largest_user = largest_user.max(format_optional(socket.user.as_deref()).len());
         });
     
         let mut widths: [usize; TABLE_COLUMNS] = [0; TABLE_COLUMNS];
         widths[PID_IDX] = PID_W;
-        widths[PORT_IDX] = PORT_W;
+        widths[PORT_IDX] = std::cmp::max(largest_port + 2, PORT_W);
         widths[STATE_IDX] = STATE_W;
-        widths[PROTOCOL_IDX] = PROTOCOL_W;
+        widths[PROTOCOL_IDX] = std::cmp::max(largest_protocol + 2, PROTOCOL_W);
         // + 2: Extra padding
         widths[UPTIME_IDX] = std::cmp::max(largest_uptime + 2, UPTIME_W);
         widths[PROCESS_IDX] = std::cmp::max(largest_file_name + 2, PROCESS_W); 
         widths[LOCAL_ADDR_IDX] = std::cmp::max(largest_local_addr + 2, LOCAL_ADDR_W);
         widths[REMOTE_ADDR_IDX] = std::cmp::max(largest_remote_addr + 2, REMOTE_ADDR_W);
+        // Unix socket paths and packet socket interfaces span both address columns unless '--fields' splits them, in which case the local address column holds them
+        if args.fields.is_some() {
+            widths[LOCAL_ADDR_IDX] = std::cmp::max(largest_unix_path + 2, widths[LOCAL_ADDR_IDX]);
+        } else {
+            let addresses_w = widths[LOCAL_ADDR_IDX] + widths[REMOTE_ADDR_IDX] + 1;
+            widths[REMOTE_ADDR_IDX] += (largest_unix_path + 2).saturating_sub(addresses_w);
+        }
+        widths[RTT_IDX] = RTT_W;
+        widths[RTT_VAR_IDX] = RTT_W;
+        widths[CWND_IDX] = CWND_W;
+        widths[RETRANSMITS_IDX] = RETRANSMITS_W;
+        widths[MSS_IDX] = MSS_W;
+        widths[CONGESTION_IDX] = std::cmp::max(largest_congestion + 2, CONGESTION_W);
+        widths[SKMEM_IDX] = std::cmp::max(largest_skmem + 2, SKMEM_W);
+        widths[PEER_IDX] = std::cmp::max(largest_peer + 2, PEER_W);
+        widths[INTERFACE_IDX] = std::cmp::max(largest_interface + 2, INTERFACE_W);
+        widths[NETNS_IDX] = NETNS_W;
+        widths[CONTAINER_IDX] = std::cmp::max(largest_container + 2, CONTAINER_W);
+        widths[UNIT_IDX] = std::cmp::max(largest_unit + 2, UNIT_W);
+        widths[ORIG_BYTES_IDX] = BYTES_W;
+        widths[RESP_BYTES_IDX] = BYTES_W;
+        widths[CMDLINE_IDX] = (largest_cmdline + 2).clamp(CMDLINE_W, CMDLINE_MAX_W);
+        widths[USER_IDX] = std::cmp::max(largest_user + 2, USER_W);
+        widths[PPID_IDX] = PPID_W;
+        widths[START_TIME_IDX] = START_TIME_W;
+        widths[RECV_Q_IDX] = QUEUE_W;
+        widths[SEND_Q_IDX] = QUEUE_W;
 
         print_socket_table_header(&widths, args.compact, &args.fields);
         for socket in socket_table {
@@ -217,10 +344,191 @@ impl Socket {
     }
 }
 
-fn sort_by<K: Ord, F: Fn(&Socket) -> K>(order: &str, table: &mut Vec<Socket>, key_field_fn: F) {
+fn sort_by<K: Ord, F: Fn(&Socket) -> K>(order: &str, table: &mut [Socket], key_field_fn: F) {
     match order {
         "asc" => table.sort_by_key(key_field_fn),
         "desc" => table.sort_by_key(|s| std::cmp::Reverse(key_field_fn(s))),
         _ => RpdError::InvalidSortOrderErr(order.to_string()).handle()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use super::*;
+    use crate::tools::socket_source::FixtureSource;
+
+    fn parse_args(flags: &[&str]) -> Args {
+        Args::parse_from(["r-port-doctor", "--source", "fixture"].iter().chain(flags))
+    }
+
+    // (PID, local port) of the fixture rows left by the filters, in table order
+    fn filter(flags: &[&str]) -> Vec<(u32, u16)> {
+        let args = parse_args(flags);
+        let mut table = FixtureSource::default().sockets;
+        Socket::filter_socket_table(&mut table, &args, args.get_argc());
+        table.iter().map(|s| (s.pid, s.port)).collect()
+    }
+
+    fn sort(flags: &[&str]) -> Vec<Socket> {
+        let args = parse_args(flags);
+        let mut table = FixtureSource::default().sockets;
+        Socket::sort_socket_table(&mut table, &args);
+        table
+    }
+
+    fn assert_sorted<K: Ord + std::fmt::Debug>(field: &str, key: impl Fn(&Socket) -> K) {
+        let ascending: Vec<K> = sort(&["--sort", "asc", field]).iter().map(&key).collect();
+        assert!(ascending.windows(2).all(|w| w[0] <= w[1]), "asc {}: {:?}", field, ascending);
+        let descending: Vec<K> = sort(&["--sort", "desc", field]).iter().map(&key).collect();
+        assert!(descending.windows(2).all(|w| w[0] >= w[1]), "desc {}: {:?}", field, descending);
+    }
+
+    #[test]
+    fn no_filter_keeps_every_row() {
+        assert_eq!(filter(&[]).len(), FixtureSource::default().sockets.len());
+    }
+
+    #[test]
+    fn filters_by_port() {
+        assert_eq!(filter(&["--port", "443"]), vec![(1200, 443), (1201, 443), (1201, 443)]);
+        assert_eq!(filter(&["--remote-port", "5432"]), vec![(3100, 49822)]);
+    }
+
+    #[test]
+    fn filters_by_pid() {
+        assert_eq!(filter(&["--pid", "3100"]), vec![(3100, 49822), (3100, 49830), (3100, 49900), (3100, 0)]);
+        assert_eq!(filter(&["--pid", "1201", "--state", "established"]), vec![(1201, 443)]);
+        assert_eq!(filter(&["--no-system"]).len(), FixtureSource::default().sockets.len() - 1);
+    }
+
+    #[test]
+    fn filters_by_state() {
+        assert_eq!(filter(&["--state", "close_wait"]), vec![(3100, 49830)]);
+        // TCP and Unix listeners
+        assert_eq!(filter(&["--state", "Listen"]).len(), 9);
+        assert_eq!(filter(&["--state", "unconnected"]), vec![(300, 0)]);
+    }
+
+    #[test]
+    fn filters_by_peer_process() {
+        assert_eq!(filter(&["--peer-process", "^post"]), vec![(3100, 0)]);
+        assert_eq!(filter(&["--peer-process", "nginx"]), Vec::<(u32, u16)>::new());
+    }
+
+    #[test]
+    fn filters_by_address() {
+        assert_eq!(filter(&["--local-address", "10.0.0.5"]), vec![(1201, 443), (1201, 443), (3100, 49830)]);
+        assert_eq!(filter(&["--remote-address", "203.0.113.10"]), vec![(1201, 443)]);
+        assert_eq!(filter(&["--ip-version", "6", "--local-address", "::1"]), vec![(3100, 49900)]);
+    }
+
+    #[test]
+    fn filters_by_netns() {
+        assert_eq!(filter(&["--netns", "4026532300"]), vec![(1200, 80), (1200, 443), (1201, 443), (1201, 443), (1200, 80)]);
+        assert_eq!(filter(&["--netns", "4026531840"]).len(), FixtureSource::default().sockets.len() - 7);
+    }
+
+    #[test]
+    fn filters_by_container() {
+        assert_eq!(filter(&["--container", "docker"]), vec![(5100, 3000)]);
+        assert_eq!(filter(&["--container", "^5f1c2b7e"]), vec![(5200, 8080)]);
+        assert_eq!(filter(&["--container", "^9e8d|^3f2a"]), vec![(5100, 3000), (5200, 8080)]);
+        assert_eq!(filter(&["--container", "cri-o"]), Vec::<(u32, u16)>::new());
+    }
+
+    #[test]
+    fn filters_by_unit_or_slice() {
+        assert_eq!(filter(&["--unit", "^postgresql"]), vec![(2300, 5432), (2300, 0)]);
+        // Socket-activated listeners carry their .socket unit
+        assert_eq!(filter(&["--unit", "^sshd"]), vec![(1, 22)]);
+        assert_eq!(filter(&["--unit", "user-1000"]), vec![(3100, 49822), (3100, 49830), (3100, 49900), (3100, 0)]);
+        // Processes outside of any unit never match
+        assert!(!filter(&["--unit", "."]).iter().any(|(pid, _)| [4, 5100, 5200, 4100, 4200].contains(pid)));
+    }
+
+    #[test]
+    fn filters_by_user_and_cmdline() {
+        assert_eq!(filter(&["--user", "^www-data$"]), vec![(1201, 443), (1201, 443)]);
+        assert_eq!(filter(&["--cmdline", "http\\.server"]), vec![(3100, 49822), (3100, 49830), (3100, 49900), (3100, 0)]);
+        // SYSTEM has no command line and never matches
+        assert!(!filter(&["--cmdline", "."]).iter().any(|(pid, _)| *pid == 4));
+    }
+
+    #[test]
+    fn filters_by_pid_tree() {
+        assert_eq!(filter(&["--pid-tree", "1200"]), vec![(1200, 80), (1200, 443), (1201, 443), (1201, 443), (1200, 80)]);
+        assert_eq!(filter(&["--pid-tree", "900"]), vec![(5100, 3000), (5200, 8080), (900, 0)]);
+        assert_eq!(filter(&["--pid-tree", "1201", "--state", "time_wait"]), vec![(1201, 443)]);
+    }
+
+    #[test]
+    fn filters_by_uptime() {
+        assert_eq!(filter(&["--younger-than", "60"]), vec![(1201, 443), (3100, 49900), (4100, 0)]);
+        assert_eq!(filter(&["--older-than", "100000"]), vec![(1, 22), (2300, 5432), (640, 53), (710, 123), (720, 5353), (2300, 0), (300, 0)]);
+    }
+
+    #[test]
+    fn filters_by_range() {
+        assert_eq!(filter(&["--range", "remote-port", "5000", "9000"]), vec![(3100, 49822), (3100, 49900)]);
+        assert_eq!(filter(&["--range", "pid", "600", "1000"]), vec![(640, 53), (710, 123), (720, 5353), (900, 0)]);
+        assert_eq!(filter(&["--range", "remote-address", "198.51.100.0", "203.0.113.255"]), vec![(1201, 443), (1201, 443)]);
+        // Rows without byte counters never fall in a range
+        assert_eq!(filter(&["--range", "resp-bytes", "60000", "2000000"]), vec![(1201, 443), (3100, 49830)]);
+        assert_eq!(filter(&["--range", "send-q", "1", "4096"]), vec![(2300, 5432)]);
+    }
+
+    #[test]
+    fn sorts_by_numeric_fields() {
+        assert_sorted("pid", |s| s.pid);
+        assert_sorted("port", |s| s.port);
+        assert_sorted("remote-port", |s| s.remote_port);
+        assert_sorted("uptime", |s| s.uptime);
+        assert_sorted("rtt", |s| s.rtt);
+        assert_sorted("rtt-var", |s| s.rtt_var);
+        assert_sorted("cwnd", |s| s.cwnd);
+        assert_sorted("retransmits", |s| s.retransmits);
+        assert_sorted("mss", |s| s.mss);
+        assert_sorted("skmem", |s| s.skmem.as_ref().map(|m| m.total()));
+        assert_sorted("netns", |s| s.netns);
+        assert_sorted("orig-bytes", |s| s.orig_bytes);
+        assert_sorted("resp-bytes", |s| s.resp_bytes);
+        assert_sorted("recv-q", |s| s.recv_q);
+        assert_sorted("send-q", |s| s.send_q);
+
+        assert_eq!(sort(&["--sort", "asc", "pid"])[0].pid, 1);
+        assert_eq!(sort(&["--sort", "desc", "port"])[0].port, 49900);
+        assert_eq!(sort(&["--sort", "asc", "uptime"])[0].uptime, 5);
+        assert_eq!(sort(&["--sort", "desc", "rtt"])[0].port, 49830);
+        assert_eq!(sort(&["--sort", "desc", "mss"])[0].port, 49822);
+        assert_eq!(sort(&["--sort", "desc", "skmem"])[0].port, 49830);
+        assert_eq!(sort(&["--sort", "desc", "netns"])[0].process_name, "java");
+        assert_eq!(sort(&["--sort", "desc", "orig-bytes"])[0].remote_port, Some(51234));
+        assert_eq!(sort(&["--sort", "asc", "resp-bytes"])[0].resp_bytes, None);
+        assert_eq!(sort(&["--sort", "desc", "recv-q"])[0].recv_q, Some(3420));
+        // Rows without TCP internals come first in ascending order
+        assert_eq!(sort(&["--sort", "asc", "cwnd"])[0].cwnd, None);
+    }
+
+    #[test]
+    fn sorts_by_text_fields() {
+        assert_sorted("process-name", |s| s.process_name.clone());
+        assert_sorted("congestion", |s| s.congestion.clone());
+        assert_sorted("container", |s| s.container.as_ref().map(|c| c.label()));
+        assert_sorted("unit", |s| s.unit.clone());
+
+        assert_eq!(sort(&["--sort", "asc", "process-name"])[0].process_name, "SYSTEM");
+        assert_eq!(sort(&["--sort", "desc", "process-name"])[0].process_name, "tcpdump");
+        assert_eq!(sort(&["--sort", "desc", "congestion"])[0].congestion.as_deref(), Some("reno"));
+        assert_eq!(sort(&["--sort", "desc", "container"])[0].process_name, "node");
+        assert_eq!(sort(&["--sort", "desc", "unit"])[0].process_name, "systemd-resolved");
+    }
+
+    #[test]
+    fn sorts_by_address_of_ip_version() {
+        assert_sorted("local-address", |s| s.local_addr.parse::<Ipv4Addr>().unwrap_or(MIN_IPV4));
+        assert_sorted("remote-address", |s| s.remote_addr.as_ref().and_then(|a| a.parse::<Ipv4Addr>().ok()).unwrap_or(MIN_IPV4));
+        assert_eq!(sort(&["--sort", "desc", "local-address"])[0].local_addr, "127.0.0.53");
+        assert_eq!(sort(&["--sort", "desc", "remote-address"])[0].remote_addr.as_deref(), Some("203.0.113.10"));
+        assert_eq!(sort(&["--ip-version", "6", "--sort", "desc", "local-address"])[0].local_addr, "::1");
+    }
+}
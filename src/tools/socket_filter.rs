@@ -0,0 +1,297 @@
+use std::{net::IpAddr, str::FromStr};
+use regex::Regex;
+use crate::tools::{args::Args, range_filter::build_range_filter, rpderror::RpdError, socket::Socket, validate_address::{parse_address_list, AddressPrefix}, where_filter::WhereFilter};
+
+pub type SocketPredicate = Box<dyn Fn(&Socket) -> bool>;
+
+// An invalid pattern is reported instead of silently filtering every row out
+fn compile_regex(flag: &str, pattern: &str) -> Regex {
+    Regex::new(pattern).unwrap_or_else(|e| RpdError::InvalidRegexErr(flag.to_string(), pattern.to_string(), e.to_string()).handle())
+}
+
+// Addresses, CIDR prefixes or comma-separated lists of both. Socket addresses are compared parsed, so '::1' and '0:0:0:0:0:0:0:1' are the same address
+fn parse_address(address: &str, ip_version: &Option<u8>, error: RpdError) -> Vec<AddressPrefix> {
+    parse_address_list(address, ip_version).unwrap_or_else(|| error.handle())
+}
+
+fn address_matches(address: &str, prefixes: &[AddressPrefix]) -> bool {
+    address.parse::<IpAddr>().is_ok_and(|address| prefixes.iter().any(|prefix| prefix.contains(address)))
+}
+
+// Comma-separated values and inclusive 'min-max' ranges, each value is kept as a range of its own. Errors name the offending item
+fn parse_value_list<T: FromStr + PartialOrd + Copy>(value: &str) -> Result<Vec<(T, T)>, (String, &'static str)> {
+    value.split(',')
+        .map(|item| {
+            let item = item.trim();
+            let error = |reason| Err((item.to_string(), reason));
+            let parse = |v: &str| v.trim().parse::<T>().ok();
+            match item.split_once('-') {
+                _ if item.is_empty() => error("empty value"),
+                Some((min, max)) if min.trim().is_empty() || max.trim().is_empty() => error("ranges need both bounds, as in 'min-max'"),
+                Some((min, max)) => match (parse(min), parse(max)) {
+                    (Some(min), Some(max)) if min > max => error("the start of the range is greater than its end"),
+                    (Some(min), Some(max)) => Ok((min, max)),
+                    _ => error("expected a number or a 'min-max' range of numbers")
+                },
+                None => match parse(item) {
+                    Some(value) => Ok((value, value)),
+                    None => error("expected a number or a 'min-max' range of numbers")
+                }
+            }
+        })
+        .collect()
+}
+
+fn value_list<T: FromStr + PartialOrd + Copy>(flag: &str, value: &str) -> Vec<(T, T)> {
+    parse_value_list(value).unwrap_or_else(|(item, reason)| RpdError::InvalidValueListErr(flag.to_string(), value.to_string(), item, reason.to_string()).handle())
+}
+
+fn in_value_list<T: PartialOrd>(list: &[(T, T)], value: T) -> bool {
+    list.iter().any(|(min, max)| value >= *min && value <= *max)
+}
+
+// Comma-separated names, empty entries are skipped. States are compared case-insensitively
+fn parse_name_list(value: &str) -> Vec<String> {
+    value.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect()
+}
+
+fn in_name_list(list: &[String], value: &str) -> bool {
+    list.iter().any(|name| name.eq_ignore_ascii_case(value))
+}
+
+// Every filter flag becomes one predicate, with its regex, address, range bounds or expression prepared once. A row is kept when all of them match
+pub fn build_socket_filters(args: &Args) -> Vec<SocketPredicate> {
+    let mut filters: Vec<SocketPredicate> = Vec::new();
+
+    // Each '--range' occurrence is validated and checked on its own
+    for range_args in args.get_ranges() {
+        filters.push(build_range_filter(range_args, &args.ip_version));
+    }
+
+    if let Some(ports) = &args.port {
+        let ports = value_list::<u16>("--port", ports);
+        filters.push(Box::new(move |s| in_value_list(&ports, s.port)));
+    }
+
+    if let Some(ports) = &args.exclude_port {
+        let ports = value_list::<u16>("--exclude-port", ports);
+        filters.push(Box::new(move |s| !in_value_list(&ports, s.port)));
+    }
+
+    if let Some(ports) = &args.remote_port {
+        let ports = value_list::<u16>("--remote-port", ports);
+        filters.push(Box::new(move |s| s.remote_port.is_some_and(|port| in_value_list(&ports, port))));
+    }
+
+    if let Some(pattern) = &args.process_name {
+        let re = compile_regex("--process-name", pattern);
+        filters.push(Box::new(move |s| re.is_match(&s.process_name)));
+    }
+
+    // Every entry is a regular expression matched like '--process-name', so 'chrome' also hides 'chrome.exe'
+    if let Some(names) = &args.exclude_process {
+        let patterns: Vec<Regex> = parse_name_list(names).iter().map(|name| compile_regex("--exclude-process", name)).collect();
+        filters.push(Box::new(move |s| !patterns.iter().any(|re| re.is_match(&s.process_name))));
+    }
+
+    if let Some(pattern) = &args.peer_process {
+        let re = compile_regex("--peer-process", pattern);
+        filters.push(Box::new(move |s| s.peer_process.as_ref().is_some_and(|peer_process| re.is_match(peer_process))));
+    }
+
+    if let Some(pattern) = &args.container {
+        let re = compile_regex("--container", pattern);
+        filters.push(Box::new(move |s| {
+            s.container.as_ref().is_some_and(|container| {
+                re.is_match(&container.id) || re.is_match(&container.runtime) || container.pod_uid.as_ref().is_some_and(|uid| re.is_match(uid))
+            })
+        }));
+    }
+
+    if let Some(pattern) = &args.unit {
+        let re = compile_regex("--unit", pattern);
+        filters.push(Box::new(move |s| [&s.unit, &s.slice].iter().any(|name| name.as_ref().is_some_and(|name| re.is_match(name)))));
+    }
+
+    if let Some(pattern) = &args.user {
+        let re = compile_regex("--user", pattern);
+        filters.push(Box::new(move |s| s.user.as_ref().is_some_and(|user| re.is_match(user))));
+    }
+
+    if let Some(pattern) = &args.cmdline {
+        let re = compile_regex("--cmdline", pattern);
+        filters.push(Box::new(move |s| s.cmdline.as_ref().is_some_and(|cmdline| re.is_match(cmdline))));
+    }
+
+    if let Some(pids) = &args.pid {
+        let pids = value_list::<u32>("--pid", pids);
+        filters.push(Box::new(move |s| in_value_list(&pids, s.pid)));
+    }
+
+    if let Some(states) = &args.state {
+        let states = parse_name_list(states);
+        filters.push(Box::new(move |s| in_name_list(&states, &s.state)));
+    }
+
+    if let Some(states) = &args.exclude_state {
+        let states = parse_name_list(states);
+        filters.push(Box::new(move |s| !in_name_list(&states, &s.state)));
+    }
+
+    if let Some(address) = &args.local_address {
+        let local_prefixes = parse_address(address, &args.ip_version, RpdError::InvalidLocalAddressErr(address.clone()));
+        filters.push(Box::new(move |s| address_matches(&s.local_addr, &local_prefixes)));
+    }
+
+    if let Some(address) = &args.remote_address {
+        let remote_prefixes = parse_address(address, &args.ip_version, RpdError::InvalidRemoteAddressErr(address.clone()));
+        filters.push(Box::new(move |s| s.remote_addr.as_ref().is_some_and(|addr| address_matches(addr, &remote_prefixes))));
+    }
+
+    if let Some(seconds) = args.older_than {
+        filters.push(Box::new(move |s| s.uptime >= seconds as u64));
+    }
+
+    if let Some(seconds) = args.younger_than {
+        filters.push(Box::new(move |s| s.uptime <= seconds as u64));
+    }
+
+    if args.no_system {
+        filters.push(Box::new(|s| s.pid != 4));
+    }
+
+    // '--netns' may name a namespace through a PID, so it is resolved here instead of for every row
+    if let Some(netns) = args.netns {
+        #[cfg(target_os = "linux")]
+        let netns = crate::tools::netns::resolve_netns(netns).unwrap_or(netns);
+        filters.push(Box::new(move |s| s.netns == Some(netns)));
+    }
+
+    if let Some(expression) = &args.where_expr {
+        let where_filter = WhereFilter::new(expression);
+        filters.push(Box::new(move |s| where_filter.matches(s)));
+    }
+
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+    use super::*;
+    use crate::tools::socket_source::FixtureSource;
+
+    fn build(flags: &[&str]) -> Vec<SocketPredicate> {
+        build_socket_filters(&Args::parse_from(["r-port-doctor", "--source", "fixture"].iter().chain(flags)))
+    }
+
+    // (PID, local port) of the fixture rows every predicate keeps, in table order
+    fn kept(flags: &[&str]) -> Vec<(u32, u16)> {
+        let filters = build(flags);
+        FixtureSource::default().sockets.iter().filter(|s| filters.iter().all(|filter| filter(s))).map(|s| (s.pid, s.port)).collect()
+    }
+
+    #[test]
+    fn builds_one_predicate_per_flag() {
+        assert!(build(&[]).is_empty());
+        assert_eq!(build(&["--port", "443", "--process-name", "nginx", "--older-than", "60", "--where", "pid > 1"]).len(), 4);
+    }
+
+    #[test]
+    fn keeps_rows_matching_every_predicate() {
+        assert_eq!(kept(&["--process-name", "^nginx$", "--port", "443"]), vec![(1200, 443), (1201, 443), (1201, 443)]);
+        assert_eq!(kept(&["--process-name", "^nginx$", "--port", "443", "--younger-than", "60"]), vec![(1201, 443)]);
+    }
+
+    #[test]
+    fn compares_addresses_parsed() {
+        assert_eq!(kept(&["-v", "6", "--local-address", "0:0:0:0:0:0:0:1"]), vec![(3100, 49900)]);
+    }
+
+    #[test]
+    fn matches_address_prefixes_and_lists() {
+        assert_eq!(kept(&["--remote-address", "203.0.113.0/24,93.184.216.34"]), vec![(1201, 443), (3100, 49830)]);
+        assert_eq!(kept(&["--local-address", "10.244.0.0/16", "--remote-address", "10.0.0.0/8"]), vec![(5200, 8080)]);
+        assert_eq!(kept(&["-v", "6", "--local-address", "::1/128"]), vec![(3100, 49900)]);
+    }
+
+    #[test]
+    fn drops_rows_without_the_filtered_field() {
+        // Only the connected Unix socket of python knows its peer, an empty pattern still needs a value to match
+        assert_eq!(kept(&["--peer-process", ""]), vec![(3100, 0)]);
+    }
+
+    #[test]
+    fn keeps_rows_in_value_lists() {
+        assert_eq!(kept(&["--port", "22,5000-6000"]), vec![(1, 22), (2300, 5432), (720, 5353)]);
+        assert_eq!(kept(&["--pid", "640,700-720"]), vec![(640, 53), (710, 123), (720, 5353)]);
+        assert_eq!(kept(&["--state", "time_wait,Close_Wait"]), vec![(1201, 443), (3100, 49830)]);
+    }
+
+    #[test]
+    fn excludes_rows_in_lists() {
+        assert_eq!(kept(&["--process-name", "^nginx$", "--exclude-port", "80,1-100"]), vec![(1200, 443), (1201, 443), (1201, 443)]);
+        assert_eq!(kept(&["--process-name", "^nginx$", "--exclude-state", "LISTEN,time_wait"]), vec![(1201, 443)]);
+    }
+
+    #[test]
+    fn excludes_processes_matching_any_pattern() {
+        let all = kept(&[]).len();
+        // Unanchored like '--process-name', 'systemd' hides systemd-resolved and systemd-journald
+        assert_eq!(kept(&["--exclude-process", "systemd"]).len(), all - 2);
+        assert!(kept(&["--exclude-process", "nginx, ^python$"]).iter().all(|(pid, _)| ![1200, 1201, 3100].contains(pid)));
+        assert_eq!(kept(&["--exclude-process", "^pyth$"]).len(), all);
+    }
+
+    #[test]
+    fn leaves_range_bounds_open() {
+        assert_eq!(kept(&["--range", "uptime", "200000", "-"]), vec![(1, 22), (640, 53), (710, 123), (720, 5353), (300, 0)]);
+        assert_eq!(kept(&["--range", "pid", "-", "4"]), vec![(4, 445), (1, 22)]);
+        // IPv6 rows stay out of an open IPv4 address range
+        assert_eq!(kept(&["--range", "remote-address", "198.51.100.0", "-"]), vec![(1201, 443), (1201, 443)]);
+    }
+
+    #[test]
+    fn keeps_rows_within_every_range() {
+        assert_eq!(build(&["--range", "uptime", "200000", "-", "--range", "pid", "700", "-"]).len(), 2);
+        assert_eq!(kept(&["--range", "uptime", "200000", "-", "--range", "pid", "700", "-"]), vec![(710, 123), (720, 5353)]);
+        assert_eq!(kept(&["--range", "port", "1", "100", "--range", "port", "50", "-"]), vec![(1200, 80), (1200, 80), (640, 53)]);
+    }
+
+    #[test]
+    fn parses_values_and_ranges() {
+        assert_eq!(parse_value_list::<u16>("80"), Ok(vec![(80, 80)]));
+        assert_eq!(parse_value_list::<u16>("80, 443 ,8000-8100"), Ok(vec![(80, 80), (443, 443), (8000, 8100)]));
+        assert_eq!(parse_value_list::<u32>("1200 - 1201"), Ok(vec![(1200, 1201)]));
+        assert_eq!(parse_value_list::<u16>("53-53"), Ok(vec![(53, 53)]));
+    }
+
+    #[test]
+    fn rejects_reversed_ranges() {
+        assert_eq!(parse_value_list::<u16>("9000-80"), Err(("9000-80".to_string(), "the start of the range is greater than its end")));
+        assert_eq!(parse_value_list::<u32>("1,300-200"), Err(("300-200".to_string(), "the start of the range is greater than its end")));
+    }
+
+    #[test]
+    fn names_the_offending_item() {
+        assert_eq!(parse_value_list::<u16>("80-"), Err(("80-".to_string(), "ranges need both bounds, as in 'min-max'")));
+        assert_eq!(parse_value_list::<u16>("-80"), Err(("-80".to_string(), "ranges need both bounds, as in 'min-max'")));
+        assert_eq!(parse_value_list::<u16>("80,,443"), Err(("".to_string(), "empty value")));
+        assert_eq!(parse_value_list::<u16>("80,http"), Err(("http".to_string(), "expected a number or a 'min-max' range of numbers")));
+        assert_eq!(parse_value_list::<u16>("1-70000"), Err(("1-70000".to_string(), "expected a number or a 'min-max' range of numbers")));
+    }
+
+    #[test]
+    fn matches_values_within_any_range() {
+        let list = parse_value_list::<u16>("22,8000-8100").unwrap();
+        assert!(in_value_list(&list, 22) && in_value_list(&list, 8000) && in_value_list(&list, 8100));
+        assert!(!in_value_list(&list, 23) && !in_value_list(&list, 8101));
+    }
+
+    #[test]
+    fn matches_names_case_insensitively() {
+        let list = parse_name_list("Established, close_wait,");
+        assert_eq!(list, vec!["Established", "close_wait"]);
+        assert!(in_name_list(&list, "ESTABLISHED") && in_name_list(&list, "CLOSE_WAIT") && !in_name_list(&list, "LISTEN"));
+    }
+}
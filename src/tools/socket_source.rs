@@ -0,0 +1,327 @@
+use std::net::Ipv6Addr;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+use crate::tools::socket::Socket;
+
+pub trait SocketSource {
+    fn get_tcp_sockets(&self, ipv6: bool) -> Vec<Socket>;
+    fn get_udp_sockets(&self, ipv6: bool) -> Vec<Socket>;
+
+    // Unix domain and packet sockets have no address family, sources that can't enumerate them return an empty table
+    fn get_unix_sockets(&self) -> Vec<Socket> {
+        Vec::new()
+    }
+
+    fn get_raw_sockets(&self, _ipv6: bool) -> Vec<Socket> {
+        Vec::new()
+    }
+
+    fn get_packet_sockets(&self) -> Vec<Socket> {
+        Vec::new()
+    }
+
+    // Called once the sockets are collected, so sources reading the live system can attach details about the owning processes
+    fn enrich_sockets(&self, _sockets: &mut [Socket]) {}
+}
+
+// GetExtendedTcpTable / GetExtendedUdpTable
+#[cfg(windows)]
+pub struct IpHelperSource;
+
+#[cfg(windows)]
+impl SocketSource for IpHelperSource {
+    fn get_tcp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        use crate::tools::tcp_table::{get_tcp_sockets, get_tcp_sockets_ipv6};
+        if ipv6 { get_tcp_sockets_ipv6() } else { get_tcp_sockets() }
+    }
+
+    fn get_udp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        use crate::tools::udp_table::{get_udp_sockets, get_udp_sockets_ipv6};
+        if ipv6 { get_udp_sockets_ipv6() } else { get_udp_sockets() }
+    }
+
+    fn enrich_sockets(&self, sockets: &mut [Socket]) {
+        crate::tools::process_info::attach_process_info(sockets);
+    }
+}
+
+// /proc/net/{tcp,tcp6,udp,udp6,unix,raw,raw6,packet}, /proc/<pid>/net holds the same tables for the network namespace of that process
+#[cfg(target_os = "linux")]
+pub struct ProcfsSource {
+    net_dir: PathBuf
+}
+
+#[cfg(target_os = "linux")]
+impl ProcfsSource {
+    pub fn for_pid(pid: u32) -> Self {
+        ProcfsSource { net_dir: PathBuf::from(format!("/proc/{}/net", pid)) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Default for ProcfsSource {
+    fn default() -> Self {
+        ProcfsSource { net_dir: PathBuf::from(crate::tools::proc_net::PROC_NET_DIR) }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SocketSource for ProcfsSource {
+    fn get_tcp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        use crate::tools::proc_net::{get_tcp_sockets, get_tcp_sockets_ipv6};
+        if ipv6 { get_tcp_sockets_ipv6(&self.net_dir) } else { get_tcp_sockets(&self.net_dir) }
+    }
+
+    fn get_udp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        use crate::tools::proc_net::{get_udp_sockets, get_udp_sockets_ipv6};
+        if ipv6 { get_udp_sockets_ipv6(&self.net_dir) } else { get_udp_sockets(&self.net_dir) }
+    }
+
+    fn get_unix_sockets(&self) -> Vec<Socket> {
+        crate::tools::proc_net::get_unix_sockets(&self.net_dir)
+    }
+
+    fn get_raw_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        use crate::tools::proc_net::{get_raw_sockets, get_raw_sockets_ipv6};
+        if ipv6 { get_raw_sockets_ipv6(&self.net_dir) } else { get_raw_sockets(&self.net_dir) }
+    }
+
+    fn get_packet_sockets(&self) -> Vec<Socket> {
+        crate::tools::proc_net::get_packet_sockets(&self.net_dir)
+    }
+
+    fn enrich_sockets(&self, sockets: &mut [Socket]) {
+        crate::tools::proc_net::enrich_linux_sockets(sockets);
+    }
+}
+
+// NETLINK_SOCK_DIAG dump, falls back to procfs when the kernel doesn't support it
+#[cfg(target_os = "linux")]
+pub struct NetlinkSource;
+
+#[cfg(target_os = "linux")]
+impl SocketSource for NetlinkSource {
+    fn get_tcp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        crate::tools::sock_diag::get_inet_diag_sockets("TCP", ipv6).unwrap_or_else(|| ProcfsSource::default().get_tcp_sockets(ipv6))
+    }
+
+    fn get_udp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        crate::tools::sock_diag::get_inet_diag_sockets("UDP", ipv6).unwrap_or_else(|| ProcfsSource::default().get_udp_sockets(ipv6))
+    }
+
+    fn get_unix_sockets(&self) -> Vec<Socket> {
+        crate::tools::sock_diag::get_unix_diag_sockets().unwrap_or_else(|| ProcfsSource::default().get_unix_sockets())
+    }
+
+    fn get_raw_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        crate::tools::sock_diag::get_inet_diag_sockets("RAW", ipv6).unwrap_or_else(|| ProcfsSource::default().get_raw_sockets(ipv6))
+    }
+
+    fn get_packet_sockets(&self) -> Vec<Socket> {
+        ProcfsSource::default().get_packet_sockets()
+    }
+
+    fn enrich_sockets(&self, sockets: &mut [Socket]) {
+        crate::tools::proc_net::enrich_linux_sockets(sockets);
+    }
+}
+
+// In-memory table, the address family of each row is taken from its local address.
+// Replayed tables use it, the sample table of the unit tests is its default
+pub struct FixtureSource {
+    pub sockets: Vec<Socket>
+}
+
+impl FixtureSource {
+    pub fn new(sockets: Vec<Socket>) -> Self {
+        FixtureSource { sockets }
+    }
+
+    fn get_protocol_sockets(&self, protocol: &str, ipv6: bool) -> Vec<Socket> {
+        self.sockets.iter()
+            .filter(|s| s.protocol == protocol && s.local_addr.parse::<Ipv6Addr>().is_ok() == ipv6)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl Default for FixtureSource {
+    fn default() -> Self {
+        let socket = |process_name: &str, pid: u32, port: u16, protocol: &'static str, local_addr: &str, remote: Option<(&str, u16)>, state: &str, uptime: u64| Socket {
+            process_name: process_name.to_string(),
+            pid,
+            port,
+            protocol,
+            local_addr: local_addr.to_string(),
+            remote_addr: remote.map(|(addr, _)| addr.to_string()),
+            remote_port: remote.map(|(_, port)| port),
+            state: state.to_string(),
+            executable_path: None,
+            uptime,
+            ..Default::default()
+        };
+        // (rtt, cwnd, mss, retransmits, congestion, allocated receive memory) as read by the netlink source
+        let with_tcp_info = |socket: Socket, (rtt, cwnd, mss, retransmits, congestion, rmem_alloc): (u32, u32, u32, u32, &str, u32)| Socket {
+            rtt: Some(rtt),
+            rtt_var: Some(rtt / 2),
+            cwnd: Some(cwnd),
+            retransmits: Some(retransmits),
+            mss: Some(mss),
+            congestion: Some(congestion.to_string()),
+            skmem: Some(crate::tools::socket::SocketMemory { rmem_alloc, rcvbuf: 131072, sndbuf: 87040, ..Default::default() }),
+            ..socket
+        };
+        let unix_socket = |process_name: &str, pid: u32, path: &str, socket_type: &str, state: &str, inode: u64, uptime: u64| Socket {
+            process_name: process_name.to_string(),
+            pid,
+            protocol: "UNIX",
+            local_addr: path.to_string(),
+            state: state.to_string(),
+            uptime,
+            inode: Some(inode),
+            socket_type: Some(socket_type.to_string()),
+            ..Default::default()
+        };
+        let container = |id: &str, runtime: &str, pod_uid: Option<&str>| Some(crate::tools::socket::Container {
+            id: id.to_string(),
+            runtime: runtime.to_string(),
+            pod_uid: pod_uid.map(|uid| uid.to_string())
+        });
+        let mut sockets = vec![
+            socket("SYSTEM", 4, 445, "TCP", "0.0.0.0", Some(("0.0.0.0", 0)), "LISTEN", 86400),
+            Socket {
+                unit: Some("sshd.socket".to_string()),
+                activates: Some("sshd@.service".to_string()),
+                ..socket("sshd.socket -> sshd@.service", 1, 22, "TCP", "0.0.0.0", Some(("0.0.0.0", 0)), "LISTEN", 259200)
+            },
+            socket("nginx", 1200, 80, "TCP", "0.0.0.0", Some(("0.0.0.0", 0)), "LISTEN", 7200),
+            socket("nginx", 1200, 443, "TCP", "0.0.0.0", Some(("0.0.0.0", 0)), "LISTEN", 7200),
+            with_tcp_info(socket("nginx", 1201, 443, "TCP", "10.0.0.5", Some(("203.0.113.10", 51234)), "ESTABLISHED", 35), (24000, 10, 1448, 2, "cubic", 2304)),
+            socket("nginx", 1201, 443, "TCP", "10.0.0.5", Some(("198.51.100.7", 60211)), "TIME_WAIT", 120),
+            Socket {
+                recv_q: Some(0),
+                send_q: Some(244),
+                ..socket("postgres", 2300, 5432, "TCP", "127.0.0.1", Some(("0.0.0.0", 0)), "LISTEN", 172800)
+            },
+            with_tcp_info(socket("python", 3100, 49822, "TCP", "127.0.0.1", Some(("127.0.0.1", 5432)), "ESTABLISHED", 3600), (45, 10, 65483, 0, "cubic", 0)),
+            Socket {
+                recv_q: Some(3420),
+                send_q: Some(0),
+                ..with_tcp_info(socket("python", 3100, 49830, "TCP", "10.0.0.5", Some(("93.184.216.34", 443)), "CLOSE_WAIT", 600), (180000, 4, 1448, 7, "bbr", 3420))
+            },
+            Socket {
+                container: container("3f2a9c1b7e4d5a6b8c9d0e1f2a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d", "docker", None),
+                ..socket("node", 5100, 3000, "TCP", "0.0.0.0", Some(("0.0.0.0", 0)), "LISTEN", 1800)
+            },
+            Socket {
+                container: container("9e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a29180f7e6d5c4b3a291807f6e5d4c3", "containerd", Some("5f1c2b7e-0a9d-4c3b-8e6f-1a2b3c4d5e6f")),
+                ..socket("java", 5200, 8080, "TCP", "10.244.1.17", Some(("10.244.2.3", 41522)), "ESTABLISHED", 240)
+            },
+            socket("nginx", 1200, 80, "TCP", "::", Some(("::", 0)), "LISTEN", 7200),
+            with_tcp_info(socket("python", 3100, 49900, "TCP", "::1", Some(("::1", 8080)), "ESTABLISHED", 10), (60, 10, 65464, 0, "reno", 768)),
+            socket("systemd-resolved", 640, 53, "UDP", "127.0.0.53", None, " ", 259200),
+            socket("chrony", 710, 123, "UDP", "0.0.0.0", None, " ", 259200),
+            socket("avahi-daemon", 720, 5353, "UDP", "::", None, " ", 259200),
+            unix_socket("dockerd", 900, "/run/docker.sock", "STREAM", "LISTEN", 9001, 86400),
+            unix_socket("postgres", 2300, "/var/run/postgresql/.s.PGSQL.5432", "STREAM", "LISTEN", 9002, 172800),
+            Socket {
+                peer_inode: Some(9010),
+                peer_pid: Some(2300),
+                peer_process: Some("postgres".to_string()),
+                ..unix_socket("python", 3100, "", "STREAM", "ESTABLISHED", 9003, 3600)
+            },
+            unix_socket("systemd-journald", 300, "/run/systemd/journal/dev-log", "DGRAM", "UNCONNECTED", 9004, 259200),
+            Socket { protocol_number: Some(1), inode: Some(9005), ..socket("ping", 4100, 0, "RAW", "0.0.0.0", None, " ", 5) },
+            Socket {
+                socket_type: Some("RAW".to_string()),
+                protocol_number: Some(0x0003),
+                inode: Some(9006),
+                ..socket("tcpdump", 4200, 0, "PACKET", "", None, " ", 900)
+            }
+        ];
+        // Listed with '--all-netns', nginx and each container run in a network namespace of their own
+        for socket in sockets.iter_mut() {
+            socket.netns = Some(match socket.process_name.as_str() {
+                "nginx" => 4026532300,
+                "node" => 4026532400,
+                "java" => 4026532500,
+                _ => 4026531840
+            });
+        }
+
+        // (process name, unit, slice)
+        let units = [
+            ("nginx", "nginx.service", "system.slice"),
+            ("postgres", "postgresql.service", "system.slice"),
+            ("python", "session-2.scope", "user-1000.slice"),
+            ("systemd-resolved", "systemd-resolved.service", "system.slice"),
+            ("chrony", "chronyd.service", "system.slice"),
+            ("avahi-daemon", "avahi-daemon.service", "system.slice"),
+            ("dockerd", "docker.service", "system.slice"),
+            ("systemd-journald", "systemd-journald.service", "system.slice")
+        ];
+        // (PID, parent PID, user, command line, start time)
+        let processes = [
+            (1, 0, "root", "/sbin/init", 1717200000),
+            (4, 0, "SYSTEM", "", 1717200000),
+            (300, 1, "root", "/usr/lib/systemd/systemd-journald", 1717200002),
+            (640, 1, "systemd-resolve", "/usr/lib/systemd/systemd-resolved", 1717200005),
+            (710, 1, "_chrony", "/usr/sbin/chronyd -F 1", 1717200005),
+            (720, 1, "avahi", "avahi-daemon: running [host.local]", 1717200006),
+            (900, 1, "root", "/usr/bin/dockerd -H fd:// --containerd=/run/containerd/containerd.sock", 1717200010),
+            (1200, 1, "root", "nginx: master process /usr/sbin/nginx -g daemon on; master_process on;", 1717452000),
+            (1201, 1200, "www-data", "nginx: worker process", 1717452000),
+            (2300, 1, "postgres", "/usr/lib/postgresql/16/bin/postgres -D /var/lib/postgresql/16/main", 1717286400),
+            (3100, 3050, "alice", "python3 -m http.server --bind ::1 8080", 1717455600),
+            (4100, 3050, "alice", "ping -c 5 example.com", 1717459195),
+            (4200, 3050, "root", "tcpdump -i any -w capture.pcap", 1717458300),
+            (5100, 900, "node", "node /app/server.js", 1717457400),
+            (5200, 900, "root", "java -jar /opt/app/app.jar", 1717458960)
+        ];
+        for socket in sockets.iter_mut() {
+            if let Some((_, unit, slice)) = units.iter().find(|(name, _, _)| *name == socket.process_name) {
+                socket.unit = Some(unit.to_string());
+                socket.slice = Some(slice.to_string());
+            }
+            if let Some((_, ppid, user, cmdline, start_time)) = processes.iter().find(|(pid, ..)| *pid == socket.pid) {
+                socket.ppid = Some(*ppid);
+                socket.user = Some(user.to_string());
+                socket.cmdline = if cmdline.is_empty() { None } else { Some(cmdline.to_string()) };
+                socket.start_time = Some(*start_time);
+            }
+        }
+
+        // (remote port, originator bytes, responder bytes) as imported from a Zeek conn.log
+        let traffic = [(51234, 5120, 1048576), (60211, 830, 16384), (443, 2048, 65536)];
+        for socket in sockets.iter_mut() {
+            if let Some((_, orig_bytes, resp_bytes)) = traffic.iter().find(|(port, _, _)| socket.remote_port == Some(*port)) {
+                socket.orig_bytes = Some(*orig_bytes);
+                socket.resp_bytes = Some(*resp_bytes);
+            }
+        }
+        FixtureSource::new(sockets)
+    }
+}
+
+impl SocketSource for FixtureSource {
+    fn get_tcp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        self.get_protocol_sockets("TCP", ipv6)
+    }
+
+    fn get_udp_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        self.get_protocol_sockets("UDP", ipv6)
+    }
+
+    fn get_unix_sockets(&self) -> Vec<Socket> {
+        self.sockets.iter().filter(|s| s.protocol == "UNIX").cloned().collect()
+    }
+
+    fn get_raw_sockets(&self, ipv6: bool) -> Vec<Socket> {
+        self.get_protocol_sockets("RAW", ipv6)
+    }
+
+    fn get_packet_sockets(&self) -> Vec<Socket> {
+        self.sockets.iter().filter(|s| s.protocol == "PACKET").cloned().collect()
+    }
+}
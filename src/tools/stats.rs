@@ -7,6 +7,9 @@ pub struct Stats {
     pub established_count: usize,
     pub listen_count: usize,
     pub udp_count: usize,
+    pub unix_count: usize,
+    pub raw_count: usize,
+    pub packet_count: usize,
     pub pid_count: usize,
     pub local_port_count: usize,
     pub remote_port_count: usize,
@@ -15,21 +18,44 @@ pub struct Stats {
     pub youngest_connection: String,
     pub oldest_connection: String,
     pub top_pid: ((u32, String), usize),
-    pub top_remote_address: (String, usize)
+    pub top_remote_address: (String, usize),
+    // Sockets per container label, sorted by count
+    pub containers: Vec<(String, usize)>,
+    pub host_count: usize,
+    // Sockets per systemd unit, sorted by count
+    pub units: Vec<(String, usize)>,
+    pub no_unit_count: usize,
+    // Bytes sent by originators and responders, None when no socket carries byte counters
+    pub traffic: Option<(u64, u64)>,
+    // Remote address that exchanged the most bytes
+    pub top_talker: (String, u64)
 }
 
-pub fn get_socket_stats(socket_table: &Vec<Socket>) -> Stats {
+fn sort_groups(group_set: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut groups: Vec<(String, usize)> = group_set.into_iter().collect();
+    groups.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+pub fn get_socket_stats(socket_table: &[Socket]) -> Stats {
     let connection_count = socket_table.len();
     let mut pid_set: HashMap<(u32, String), usize> = HashMap::new();
     let mut local_addr_set: HashMap<String, usize> = HashMap::new();
     let mut remote_addr_set: HashMap<String, usize> = HashMap::new();
     let mut local_port_set: HashMap<u16, usize> = HashMap::new();
     let mut remote_port_set: HashMap<u16, usize> = HashMap::new();
+    let mut container_set: HashMap<String, usize> = HashMap::new();
+    let mut unit_set: HashMap<String, usize> = HashMap::new();
+    let mut talker_set: HashMap<String, u64> = HashMap::new();
+    let mut traffic: Option<(u64, u64)> = None;
     
     let udp_count = socket_table.iter().filter(|s| s.protocol == "UDP").count();
     let tcp_count = socket_table.iter().filter(|s| s.protocol == "TCP").count();
-    let established_count = socket_table.iter().filter(|s| s.state == "ESTABLISHED").count();
-    let listen_count = socket_table.iter().filter(|s| s.state == "LISTEN").count();
+    let unix_count = socket_table.iter().filter(|s| s.protocol == "UNIX").count();
+    let raw_count = socket_table.iter().filter(|s| s.protocol == "RAW").count();
+    let packet_count = socket_table.iter().filter(|s| s.protocol == "PACKET").count();
+    let established_count = socket_table.iter().filter(|s| s.protocol == "TCP" && s.state == "ESTABLISHED").count();
+    let listen_count = socket_table.iter().filter(|s| s.protocol == "TCP" && s.state == "LISTEN").count();
 
     let mut youngest_connection: u64 = u64::MAX;
     let mut oldest_connection: u64 = u64::MIN;
@@ -50,6 +76,22 @@ pub fn get_socket_stats(socket_table: &Vec<Socket>) -> Stats {
             *remote_addr_set.entry(addr.clone()).or_insert(0) += 1;
         }
 
+        if let Some(container) = &s.container {
+            *container_set.entry(container.label()).or_insert(0) += 1;
+        }
+        if let Some(unit) = &s.unit {
+            *unit_set.entry(unit.clone()).or_insert(0) += 1;
+        }
+
+        if s.orig_bytes.is_some() || s.resp_bytes.is_some() {
+            let (orig_bytes, resp_bytes) = (s.orig_bytes.unwrap_or(0), s.resp_bytes.unwrap_or(0));
+            let totals = traffic.get_or_insert((0, 0));
+            *totals = (totals.0 + orig_bytes, totals.1 + resp_bytes);
+            if let Some(addr) = &s.remote_addr {
+                *talker_set.entry(addr.clone()).or_insert(0) += orig_bytes + resp_bytes;
+            }
+        }
+
         youngest_connection = std::cmp::min(youngest_connection, s.uptime);
         oldest_connection = std::cmp::max(oldest_connection, s.uptime);
     });
@@ -66,12 +108,22 @@ pub fn get_socket_stats(socket_table: &Vec<Socket>) -> Stats {
         }
     });
 
+    let top_talker = talker_set.into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+        .unwrap_or_default();
+
+    let host_count = connection_count - container_set.values().sum::<usize>();
+    let no_unit_count = connection_count - unit_set.values().sum::<usize>();
+
     Stats {
         connection_count,
         tcp_count,
         established_count,
         listen_count,
         udp_count,
+        unix_count,
+        raw_count,
+        packet_count,
         pid_count: pid_set.len(),
         local_port_count: local_port_set.len(),
         remote_port_count: remote_port_set.len(),
@@ -80,6 +132,76 @@ pub fn get_socket_stats(socket_table: &Vec<Socket>) -> Stats {
         youngest_connection: get_formatted_uptime(&Some("human".to_string()), youngest_connection),
         oldest_connection: get_formatted_uptime(&Some("human".to_string()), oldest_connection),
         top_pid,
-        top_remote_address
+        top_remote_address,
+        containers: sort_groups(container_set),
+        host_count,
+        units: sort_groups(unit_set),
+        no_unit_count,
+        traffic,
+        top_talker
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::socket_source::FixtureSource;
+
+    fn fixture_stats() -> Stats {
+        get_socket_stats(&FixtureSource::default().sockets)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn counts_sockets_by_protocol_and_state() {
+        let stats = fixture_stats();
+        assert_eq!(stats.connection_count, 22);
+        assert_eq!((stats.tcp_count, stats.udp_count, stats.unix_count), (13, 3, 4));
+        assert_eq!((stats.raw_count, stats.packet_count), (1, 1));
+        assert_eq!((stats.established_count, stats.listen_count), (4, 7));
+    }
+
+    #[test]
+    fn counts_distinct_processes_ports_and_addresses() {
+        let stats = fixture_stats();
+        assert_eq!(stats.pid_count, 15);
+        assert_eq!(stats.local_port_count, 14);
+        assert_eq!(stats.remote_port_count, 7);
+        assert_eq!(stats.local_address_count, 11);
+        assert_eq!(stats.remote_address_count, 8);
+        assert_eq!(stats.top_pid, ((3100, "python".to_string()), 4));
+        assert_eq!(stats.top_remote_address.1, 1);
+    }
+
+    #[test]
+    fn breaks_sockets_down_per_container() {
+        let stats = fixture_stats();
+        assert_eq!(stats.containers, vec![("containerd:9e8d7c6b5a4f".to_string(), 1), ("docker:3f2a9c1b7e4d".to_string(), 1)]);
+        assert_eq!(stats.host_count, 20);
+    }
+
+    #[test]
+    fn breaks_sockets_down_per_unit() {
+        let stats = fixture_stats();
+        let units: Vec<(&str, usize)> = stats.units.iter().map(|(unit, count)| (unit.as_str(), *count)).collect();
+        assert_eq!(units[..4], [("nginx.service", 5), ("session-2.scope", 4), ("postgresql.service", 2), ("avahi-daemon.service", 1)]);
+        assert_eq!(units.len(), 9);
+        assert_eq!(stats.no_unit_count, 5);
+    }
+
+    #[test]
+    fn sums_traffic_of_sockets_with_byte_counters() {
+        let stats = fixture_stats();
+        assert_eq!(stats.traffic, Some((5120 + 830 + 2048, 1048576 + 16384 + 65536)));
+        assert_eq!(stats.top_talker, ("203.0.113.10".to_string(), 5120 + 1048576));
+        // Live tables carry no byte counters
+        let mut sockets = FixtureSource::default().sockets;
+        sockets.iter_mut().for_each(|s| (s.orig_bytes, s.resp_bytes) = (None, None));
+        assert_eq!(get_socket_stats(&sockets).traffic, None);
+    }
+
+    #[test]
+    fn reports_uptime_bounds() {
+        let stats = fixture_stats();
+        assert_eq!(stats.youngest_connection, get_formatted_uptime(&Some("human".to_string()), 5));
+        assert_eq!(stats.oldest_connection, get_formatted_uptime(&Some("human".to_string()), 259200));
+    }
+}
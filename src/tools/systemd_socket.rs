@@ -0,0 +1,206 @@
+use std::{collections::HashSet, fs, net::IpAddr, path::Path};
+use crate::tools::socket::Socket;
+
+// Searched in order of precedence, the first file found for a unit name masks the others
+const UNIT_DIRS: [&str; 7] = [
+    "/etc/systemd/system",
+    "/run/systemd/transient",
+    "/run/systemd/system",
+    "/run/systemd/generator",
+    "/usr/local/lib/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system"
+];
+
+enum ListenAddress {
+    // A bare port listens on every address of both IP versions
+    Port(u16),
+    Inet(String, u16),
+    Path(String)
+}
+
+struct Listener {
+    directive: String,
+    address: ListenAddress
+}
+
+pub struct SocketUnit {
+    pub name: String,
+    pub service: String,
+    listeners: Vec<Listener>
+}
+
+// '80', '127.0.0.1:53', '[::]:443', '0.0.0.0:80%eth0', '/run/foo.sock' or '@abstract'
+fn parse_listen_address(value: &str) -> Option<ListenAddress> {
+    if value.starts_with('/') || value.starts_with('@') {
+        return Some(ListenAddress::Path(value.to_string()))
+    }
+    if let Ok(port) = value.parse::<u16>() {
+        return Some(ListenAddress::Port(port))
+    }
+    let (host, port) = value.rsplit_once(':')?;
+    let port = port.split('%').next()?.parse::<u16>().ok()?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let host = host.split('%').next().unwrap_or(host);
+    Some(ListenAddress::Inet(host.parse::<IpAddr>().ok()?.to_string(), port))
+}
+
+fn parse_socket_unit(name: &str, path: &Path) -> Option<SocketUnit> {
+    parse_socket_unit_content(name, &fs::read_to_string(path).ok()?)
+}
+
+fn parse_socket_unit_content(name: &str, content: &str) -> Option<SocketUnit> {
+    let mut in_socket_section = false;
+    let mut listeners: Vec<Listener> = Vec::new();
+    let mut service: Option<String> = None;
+    let mut accept = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_socket_section = line == "[Socket]";
+            continue
+        }
+        let Some((key, value)) = line.split_once('=') else { continue };
+        if !in_socket_section {
+            continue
+        }
+        let (key, value) = (key.trim(), value.trim());
+        match key {
+            "ListenStream" | "ListenDatagram" | "ListenSequentialPacket" => {
+                if let Some(address) = parse_listen_address(value) {
+                    listeners.push(Listener { directive: key.to_string(), address });
+                }
+            }
+            "Service" => service = Some(value.to_string()),
+            "Accept" => accept = matches!(value, "yes" | "true" | "1" | "on"),
+            _ => {}
+        }
+    }
+    // Accept=yes spawns an instance of the '<name>@.service' template for every connection
+    let stem = name.strip_suffix(".socket")?;
+    let service = service.unwrap_or_else(|| if accept { format!("{}@.service", stem) } else { format!("{}.service", stem) });
+    Some(SocketUnit { name: name.to_string(), service, listeners })
+}
+
+pub fn get_socket_units() -> Vec<SocketUnit> {
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut units: Vec<SocketUnit> = Vec::new();
+    for dir in UNIT_DIRS {
+        let Ok(entries) = fs::read_dir(dir) else { continue };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".socket") || !seen.insert(name.clone()) {
+                continue
+            }
+            if let Some(unit) = parse_socket_unit(&name, &entry.path()) {
+                units.push(unit);
+            }
+        }
+    }
+    units
+}
+
+fn is_unspecified(addr: &str) -> bool {
+    addr.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified())
+}
+
+impl Listener {
+    fn matches(&self, socket: &Socket) -> bool {
+        let directive = match (socket.protocol, socket.socket_type.as_deref()) {
+            ("TCP", _) | ("UNIX", Some("STREAM")) => "ListenStream",
+            ("UDP", _) | ("UNIX", Some("DGRAM")) => "ListenDatagram",
+            ("UNIX", Some("SEQPACKET")) => "ListenSequentialPacket",
+            _ => return false
+        };
+        if self.directive != directive {
+            return false
+        }
+        match (&self.address, socket.protocol) {
+            (ListenAddress::Path(path), "UNIX") => *path == socket.local_addr,
+            (ListenAddress::Port(port), "TCP" | "UDP") => *port == socket.port,
+            (ListenAddress::Inet(addr, port), "TCP" | "UDP") => {
+                *port == socket.port && (*addr == socket.local_addr || (is_unspecified(addr) && is_unspecified(&socket.local_addr)))
+            }
+            _ => false
+        }
+    }
+}
+
+// Listeners created through socket activation are held by PID 1, so the '.socket' unit declaring them is shown instead of systemd itself
+pub fn attach_socket_units(sockets: &mut [Socket]) {
+    if !sockets.iter().any(|s| s.pid == 1) {
+        return
+    }
+    let units = get_socket_units();
+    for socket in sockets.iter_mut().filter(|s| s.pid == 1) {
+        let Some(unit) = units.iter().find(|unit| unit.listeners.iter().any(|listener| listener.matches(socket))) else { continue };
+        socket.process_name = format!("{} -> {}", unit.name, unit.service);
+        socket.unit = Some(unit.name.clone());
+        socket.activates = Some(unit.service.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inet(value: &str) -> Option<(String, u16)> {
+        match parse_listen_address(value)? {
+            ListenAddress::Inet(addr, port) => Some((addr, port)),
+            _ => None
+        }
+    }
+
+    fn listener_socket(protocol: &'static str, local_addr: &str, port: u16, socket_type: Option<&str>) -> Socket {
+        Socket {
+            pid: 1,
+            protocol,
+            local_addr: local_addr.to_string(),
+            port,
+            socket_type: socket_type.map(|t| t.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_listen_addresses() {
+        assert!(matches!(parse_listen_address("80"), Some(ListenAddress::Port(80))));
+        assert_eq!(inet("127.0.0.1:53"), Some(("127.0.0.1".to_string(), 53)));
+        assert_eq!(inet("[::]:443"), Some(("::".to_string(), 443)));
+        assert_eq!(inet("0.0.0.0:80%eth0"), Some(("0.0.0.0".to_string(), 80)));
+        assert_eq!(inet("[fe80::1%eth0]:8080"), Some(("fe80::1".to_string(), 8080)));
+        assert!(matches!(parse_listen_address("/run/foo.sock"), Some(ListenAddress::Path(path)) if path == "/run/foo.sock"));
+        assert!(matches!(parse_listen_address("@abstract"), Some(ListenAddress::Path(path)) if path == "@abstract"));
+        assert!(parse_listen_address("localhost:80").is_none());
+        assert!(parse_listen_address("vsock:2:1234").is_none());
+    }
+
+    #[test]
+    fn reads_listeners_of_socket_section_only() {
+        let content = "[Unit]\nDescription=OpenBSD Secure Shell server socket\n\n[Socket]\nListenStream=22\nListenDatagram = 127.0.0.1:514\nAccept=yes\n\n[Install]\nWantedBy=sockets.target\n";
+        let unit = parse_socket_unit_content("sshd.socket", content).unwrap();
+        assert_eq!((unit.name.as_str(), unit.service.as_str()), ("sshd.socket", "sshd@.service"));
+        assert_eq!(unit.listeners.iter().map(|l| l.directive.as_str()).collect::<Vec<_>>(), vec!["ListenStream", "ListenDatagram"]);
+    }
+
+    #[test]
+    fn names_activated_service() {
+        let unit = parse_socket_unit_content("cups.socket", "[Socket]\nListenStream=/run/cups/cups.sock\n").unwrap();
+        assert_eq!(unit.service, "cups.service");
+        let unit = parse_socket_unit_content("foo.socket", "[Socket]\nListenStream=8080\nService=bar.service\n").unwrap();
+        assert_eq!(unit.service, "bar.service");
+    }
+
+    #[test]
+    fn matches_listeners_by_directive_and_address() {
+        let unit = parse_socket_unit_content("demo.socket", "[Socket]\nListenStream=22\nListenDatagram=[::]:514\nListenStream=/run/demo.sock\n").unwrap();
+        let matches = |socket: Socket| unit.listeners.iter().any(|listener| listener.matches(&socket));
+        assert!(matches(listener_socket("TCP", "0.0.0.0", 22, None)));
+        assert!(matches(listener_socket("TCP", "::", 22, None)));
+        assert!(!matches(listener_socket("UDP", "0.0.0.0", 22, None)));
+        // Unspecified addresses of either IP version stand for each other
+        assert!(matches(listener_socket("UDP", "0.0.0.0", 514, None)));
+        assert!(!matches(listener_socket("UDP", "127.0.0.1", 514, None)));
+        assert!(matches(listener_socket("UNIX", "/run/demo.sock", 0, Some("STREAM"))));
+        assert!(!matches(listener_socket("UNIX", "/run/demo.sock", 0, Some("DGRAM"))));
+    }
+}
@@ -1,13 +1,130 @@
-use std::{net::{Ipv4Addr, Ipv6Addr}, str::FromStr};
-
-pub fn validate_address(address: &String, version: &Option<u8>) -> bool {
-    if let Some(v) = version {
-        match v {
-            4 => Ipv4Addr::from_str(&address).is_ok(),
-            6 => Ipv6Addr::from_str(&address).is_ok(),
-            _ => false
+use std::{net::{IpAddr, Ipv4Addr, Ipv6Addr}, str::FromStr};
+
+// Network given in CIDR notation ('10.0.0.0/8', 'fe80::/10'), a plain address is a prefix of its full length
+#[derive(Clone, Copy)]
+pub struct AddressPrefix {
+    network: IpAddr,
+    prefix_len: u8
+}
+
+impl AddressPrefix {
+    pub fn parse(value: &str) -> Option<Self> {
+        let (address, prefix_len) = match value.split_once('/') {
+            Some((address, prefix_len)) => (IpAddr::from_str(address.trim()).ok()?, Some(prefix_len.trim().parse::<u8>().ok()?)),
+            None => (IpAddr::from_str(value.trim()).ok()?, None)
+        };
+        let max_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_len);
+        if prefix_len > max_len {
+            return None
         }
-    } else {
-        Ipv4Addr::from_str(&address).is_ok()
+        // Host bits are cleared, so '10.1.2.3/8' is the same network as '10.0.0.0/8'
+        Some(AddressPrefix { network: mask_address(address, prefix_len), prefix_len })
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.network.is_ipv4()
+    }
+
+    // IPv4-mapped IPv6 addresses ('::ffff:10.0.0.1') are matched against IPv4 prefixes. Mapped prefixes ('::ffff:10.0.0.0/104',
+    // '::ffff:0:0/96') are their IPv4 network, and other IPv6 prefixes see IPv4 addresses in their mapped form
+    pub fn contains(&self, address: IpAddr) -> bool {
+        let (network, prefix_len) = self.canonical();
+        let address = match (network, address.to_canonical()) {
+            (IpAddr::V4(_), IpAddr::V4(v4)) => IpAddr::V4(v4),
+            (IpAddr::V6(_), IpAddr::V4(v4)) => IpAddr::V6(v4.to_ipv6_mapped()),
+            (IpAddr::V6(_), IpAddr::V6(v6)) => IpAddr::V6(v6),
+            (IpAddr::V4(_), IpAddr::V6(_)) => return false
+        };
+        mask_address(address, prefix_len) == network
+    }
+
+    fn canonical(&self) -> (IpAddr, u8) {
+        match self.network {
+            IpAddr::V6(v6) if self.prefix_len >= 96 => match v6.to_ipv4_mapped() {
+                Some(v4) => (IpAddr::V4(v4), self.prefix_len - 96),
+                None => (self.network, self.prefix_len)
+            },
+            _ => (self.network, self.prefix_len)
+        }
+    }
+}
+
+fn mask_address(address: IpAddr, prefix_len: u8) -> IpAddr {
+    match address {
+        IpAddr::V4(v4) => IpAddr::V4(Ipv4Addr::from(u32::from(v4) & u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0))),
+        IpAddr::V6(v6) => IpAddr::V6(Ipv6Addr::from(u128::from(v6) & u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0)))
+    }
+}
+
+// Comma-separated list of addresses and CIDR prefixes, all of the '--ip-version' family (IPv4 by default)
+pub fn parse_address_list(addresses: &str, version: &Option<u8>) -> Option<Vec<AddressPrefix>> {
+    let ipv4 = match version {
+        Some(4) | None => true,
+        Some(6) => false,
+        _ => return None
+    };
+    addresses.split(',')
+        .map(|address| AddressPrefix::parse(address).filter(|prefix| prefix.is_ipv4() == ipv4))
+        .collect()
+}
+
+pub fn validate_address(address: &str, version: &Option<u8>) -> bool {
+    parse_address_list(address, version).is_some()
+}
+
+// Range bounds take a single address, without prefix length
+pub fn validate_single_address(address: &str, version: &Option<u8>) -> bool {
+    !address.contains(['/', ',']) && validate_address(address, version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(prefix: &str, address: &str) -> bool {
+        AddressPrefix::parse(prefix).unwrap().contains(address.parse().unwrap())
+    }
+
+    #[test]
+    fn matches_addresses_within_the_prefix() {
+        assert!(contains("10.0.0.0/8", "10.244.1.7"));
+        assert!(!contains("10.0.0.0/8", "11.0.0.1"));
+        assert!(contains("10.1.2.3/8", "10.0.0.1"));
+        assert!(contains("0.0.0.0/0", "203.0.113.10"));
+        assert!(contains("fe80::/10", "fe80::1"));
+        assert!(!contains("fe80::/10", "fd00::1"));
+        assert!(contains("::1", "0:0:0:0:0:0:0:1"));
+    }
+
+    #[test]
+    fn matches_mapped_addresses_and_prefixes() {
+        assert!(contains("10.0.0.0/8", "::ffff:10.0.0.5"));
+        assert!(contains("::ffff:10.0.0.5", "::ffff:10.0.0.5"));
+        assert!(contains("::ffff:10.0.0.5", "10.0.0.5"));
+        assert!(!contains("::ffff:10.0.0.5", "10.0.0.6"));
+        assert!(contains("::ffff:10.0.0.0/104", "10.1.2.3"));
+        assert!(contains("::ffff:0:0/96", "::ffff:192.168.1.1"));
+        assert!(contains("::ffff:0:0/96", "192.168.1.1"));
+        assert!(!contains("::ffff:0:0/96", "fe80::1"));
+        assert!(contains("::/0", "::ffff:10.0.0.5"));
+        assert!(!contains("10.0.0.0/8", "fe80::1"));
+    }
+
+    #[test]
+    fn parses_lists_of_the_requested_family() {
+        assert_eq!(parse_address_list("10.0.0.0/8,192.168.1.1", &None).map(|list| list.len()), Some(2));
+        assert!(parse_address_list("10.0.0.0/8,fe80::/10", &None).is_none());
+        assert!(parse_address_list("fe80::/10", &Some(6)).is_some());
+        assert!(parse_address_list("10.0.0.0/33", &None).is_none());
+        assert!(parse_address_list("10.0.0.0/8,", &None).is_none());
+    }
+
+    #[test]
+    fn takes_single_addresses_as_range_bounds() {
+        assert!(validate_single_address("10.0.0.1", &None));
+        assert!(!validate_single_address("10.0.0.0/8", &None));
+        assert!(!validate_single_address("10.0.0.1,10.0.0.2", &None));
+        assert!(!validate_single_address("::1", &None));
     }
-}
\ No newline at end of file
+}
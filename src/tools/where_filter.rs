@@ -0,0 +1,614 @@
+use std::{borrow::Cow, cmp::Ordering, net::IpAddr};
+use regex::{Regex, RegexBuilder};
+use crate::tools::{rpderror::RpdError, socket::Socket, validate_address::AddressPrefix};
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    Number,
+    Text,
+    Address
+}
+
+// Every field of a socket that can be used in '--where', named like the '--fields' columns ('_' is accepted for '-')
+const WHERE_FIELDS: [(&str, FieldType); 38] = [
+    ("pid", FieldType::Number),
+    ("process-name", FieldType::Text),
+    ("port", FieldType::Number),
+    ("protocol", FieldType::Text),
+    ("local-address", FieldType::Address),
+    ("remote-address", FieldType::Address),
+    ("remote-port", FieldType::Number),
+    ("state", FieldType::Text),
+    ("executable-path", FieldType::Text),
+    ("uptime", FieldType::Number),
+    ("uptime-bound", FieldType::Text),
+    ("cmdline", FieldType::Text),
+    ("user", FieldType::Text),
+    ("ppid", FieldType::Number),
+    ("start-time", FieldType::Number),
+    ("inode", FieldType::Number),
+    ("socket-type", FieldType::Text),
+    ("protocol-number", FieldType::Number),
+    ("interface", FieldType::Text),
+    ("netns", FieldType::Number),
+    ("container", FieldType::Text),
+    ("unit", FieldType::Text),
+    ("slice", FieldType::Text),
+    ("activates", FieldType::Text),
+    ("peer-inode", FieldType::Number),
+    ("peer-pid", FieldType::Number),
+    ("peer-process", FieldType::Text),
+    ("rtt", FieldType::Number),
+    ("rtt-var", FieldType::Number),
+    ("cwnd", FieldType::Number),
+    ("retransmits", FieldType::Number),
+    ("mss", FieldType::Number),
+    ("congestion", FieldType::Text),
+    ("skmem", FieldType::Number),
+    ("recv-q", FieldType::Number),
+    ("send-q", FieldType::Number),
+    ("orig-bytes", FieldType::Number),
+    ("resp-bytes", FieldType::Number)
+];
+
+enum FieldValue<'a> {
+    Number(u64),
+    Text(Cow<'a, str>)
+}
+
+fn get_field_value<'a>(socket: &'a Socket, field: &str) -> Option<FieldValue<'a>> {
+    let number = |value: Option<u64>| value.map(FieldValue::Number);
+    let text = |value: Option<&'a String>| value.map(|v| FieldValue::Text(Cow::Borrowed(v.as_str())));
+    match field {
+        "pid" => number(Some(socket.pid as u64)),
+        "process-name" => text(Some(&socket.process_name)),
+        "port" => number(Some(socket.port as u64)),
+        "protocol" => Some(FieldValue::Text(Cow::Borrowed(socket.protocol))),
+        "local-address" => text(Some(&socket.local_addr)),
+        "remote-address" => text(socket.remote_addr.as_ref()),
+        "remote-port" => number(socket.remote_port.map(u64::from)),
+        // UDP sockets carry a blank state
+        "state" => text(Some(&socket.state).filter(|s| !s.trim().is_empty())),
+        "executable-path" => text(socket.executable_path.as_ref()),
+        "uptime" => number(Some(socket.uptime)),
+        "uptime-bound" => text(socket.uptime_bound.as_ref()),
+        "cmdline" => text(socket.cmdline.as_ref()),
+        "user" => text(socket.user.as_ref()),
+        "ppid" => number(socket.ppid.map(u64::from)),
+        "start-time" => number(socket.start_time),
+        "inode" => number(socket.inode),
+        "socket-type" => text(socket.socket_type.as_ref()),
+        "protocol-number" => number(socket.protocol_number.map(u64::from)),
+        "interface" => text(socket.interface.as_ref()),
+        "netns" => number(socket.netns),
+        "container" => socket.container.as_ref().map(|c| FieldValue::Text(Cow::Owned(c.label()))),
+        "unit" => text(socket.unit.as_ref()),
+        "slice" => text(socket.slice.as_ref()),
+        "activates" => text(socket.activates.as_ref()),
+        "peer-inode" => number(socket.peer_inode),
+        "peer-pid" => number(socket.peer_pid.map(u64::from)),
+        "peer-process" => text(socket.peer_process.as_ref()),
+        "rtt" => number(socket.rtt.map(u64::from)),
+        "rtt-var" => number(socket.rtt_var.map(u64::from)),
+        "cwnd" => number(socket.cwnd.map(u64::from)),
+        "retransmits" => number(socket.retransmits.map(u64::from)),
+        "mss" => number(socket.mss.map(u64::from)),
+        "congestion" => text(socket.congestion.as_ref()),
+        "skmem" => number(socket.skmem.as_ref().map(|m| m.total())),
+        "recv-q" => number(socket.recv_q.map(u64::from)),
+        "send-q" => number(socket.send_q.map(u64::from)),
+        "orig-bytes" => number(socket.orig_bytes),
+        "resp-bytes" => number(socket.resp_bytes),
+        _ => None
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch
+}
+
+#[derive(Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Operator(Operator),
+    LeftParen,
+    RightParen,
+    Comma,
+    End
+}
+
+// 'start' and 'len' are byte offsets into the expression, used to point at the token in error messages
+#[derive(Clone)]
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    len: usize
+}
+
+impl Token {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+}
+
+enum Literal {
+    Number(u64),
+    Text(String),
+    Address(IpAddr),
+    Prefix(AddressPrefix)
+}
+
+// Single value or inclusive range, either bound of a range may be left open ('1024..', '..1023')
+enum Item {
+    Single(Literal),
+    Range(Option<Literal>, Option<Literal>)
+}
+
+enum Predicate {
+    Compare(Operator, Literal),
+    Regex(Regex, bool),
+    In(Vec<Item>, bool)
+}
+
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Comparison(&'static str, Predicate)
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !"()=!<>~,\"'".contains(c)
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, WhereError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue
+        }
+        let kind = match c {
+            '(' => { chars.next(); TokenKind::LeftParen }
+            ')' => { chars.next(); TokenKind::RightParen }
+            ',' => { chars.next(); TokenKind::Comma }
+            '"' | '\'' => {
+                chars.next();
+                let mut value = String::new();
+                let mut closed = false;
+                while let Some((_, next)) = chars.next() {
+                    match next {
+                        '\\' if chars.peek().is_some_and(|&(_, escaped)| escaped == c || escaped == '\\') => value.push(chars.next().unwrap().1),
+                        _ if next == c => { closed = true; break }
+                        _ => value.push(next)
+                    }
+                }
+                if !closed {
+                    return Err(WhereError::new(start, expression.len() - start, "unterminated string"))
+                }
+                TokenKind::Quoted(value)
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                chars.next();
+                let followed_by = |chars: &mut std::iter::Peekable<std::str::CharIndices>, expected: char| {
+                    chars.next_if(|&(_, next)| next == expected).is_some()
+                };
+                let operator = match c {
+                    '=' => { followed_by(&mut chars, '='); Operator::Eq }
+                    '!' if followed_by(&mut chars, '=') => Operator::Ne,
+                    '!' if followed_by(&mut chars, '~') => Operator::NotMatch,
+                    '!' => return Err(WhereError::new(start, 1, "expected '!=' or '!~', use 'not' to negate an expression")),
+                    '<' => if followed_by(&mut chars, '=') { Operator::Le } else { Operator::Lt },
+                    '>' => if followed_by(&mut chars, '=') { Operator::Ge } else { Operator::Gt },
+                    _ => Operator::Match
+                };
+                TokenKind::Operator(operator)
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some((_, next)) = chars.next_if(|&(_, next)| is_word_char(next)) {
+                    word.push(next);
+                }
+                TokenKind::Word(word)
+            }
+        };
+        let end = chars.peek().map_or(expression.len(), |&(i, _)| i);
+        tokens.push(Token { kind, start, len: end - start });
+    }
+    tokens.push(Token { kind: TokenKind::End, start: expression.len(), len: 1 });
+    Ok(tokens)
+}
+
+// Position of the offending token in the expression and what is wrong with it
+#[derive(Debug, PartialEq)]
+pub struct WhereError {
+    pub start: usize,
+    pub len: usize,
+    pub message: String
+}
+
+impl WhereError {
+    fn new(start: usize, len: usize, message: &str) -> Self {
+        WhereError { start, len, message: message.to_string() }
+    }
+}
+
+// Recursive descent over 'or' < 'and' < 'not' precedence levels
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<Token>,
+    position: usize
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].clone();
+        if token.kind != TokenKind::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error_at<T>(&self, token: &Token, message: &str) -> Result<T, WhereError> {
+        Err(WhereError::new(token.start, token.len, message))
+    }
+
+    fn describe(&self, token: &Token) -> String {
+        match token.kind {
+            TokenKind::End => "end of expression".to_string(),
+            _ => format!("'{}'", &self.expression[token.start..token.start + token.len])
+        }
+    }
+
+    fn expect_right_paren(&mut self) -> Result<(), WhereError> {
+        if self.peek().kind != TokenKind::RightParen {
+            return self.error_at(self.peek(), &format!("expected ')', found {}", self.describe(self.peek())))
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, WhereError> {
+        let mut expr = self.parse_and()?;
+        while self.peek().is_keyword("or") {
+            self.next();
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, WhereError> {
+        let mut expr = self.parse_unary()?;
+        while self.peek().is_keyword("and") {
+            self.next();
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, WhereError> {
+        if self.peek().is_keyword("not") {
+            self.next();
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)))
+        }
+        if self.peek().kind == TokenKind::LeftParen {
+            self.next();
+            let expr = self.parse_or()?;
+            self.expect_right_paren()?;
+            return Ok(expr)
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, WhereError> {
+        let field_token = self.next();
+        let TokenKind::Word(name) = &field_token.kind else {
+            return self.error_at(&field_token, &format!("expected a field name, found {}", self.describe(&field_token)))
+        };
+        let name = name.to_lowercase().replace('_', "-");
+        let Some(&(field, field_type)) = WHERE_FIELDS.iter().find(|(field, _)| *field == name) else {
+            return self.error_at(&field_token, &format!("unknown field '{}'", name))
+        };
+
+        let operator_token = self.next();
+        let negated = operator_token.is_keyword("not");
+        let operator_token = if negated { self.next() } else { operator_token };
+        if operator_token.is_keyword("in") {
+            return Ok(Expr::Comparison(field, Predicate::In(self.parse_items(field, field_type)?, negated)))
+        }
+        let operator = match &operator_token.kind {
+            TokenKind::Operator(operator) if !negated => *operator,
+            _ if negated => return self.error_at(&operator_token, &format!("expected 'in' after 'not', found {}", self.describe(&operator_token))),
+            _ => return self.error_at(&operator_token, &format!("expected an operator (=, !=, <, <=, >, >=, ~, !~, in) after '{}', found {}", field, self.describe(&operator_token)))
+        };
+
+        let value_token = self.next();
+        let value = match &value_token.kind {
+            TokenKind::Word(value) | TokenKind::Quoted(value) => value.clone(),
+            _ => return self.error_at(&value_token, &format!("expected a value, found {}", self.describe(&value_token)))
+        };
+        let predicate = match operator {
+            Operator::Match | Operator::NotMatch => {
+                if !matches!(field_type, FieldType::Text | FieldType::Address) {
+                    return self.error_at(&operator_token, &format!("'~' and '!~' need a text field, '{}' is not one", field))
+                }
+                // Case-insensitive like every other text comparison
+                match RegexBuilder::new(&value).case_insensitive(true).build() {
+                    Ok(re) => Predicate::Regex(re, operator == Operator::NotMatch),
+                    Err(_) => return self.error_at(&value_token, "invalid regular expression")
+                }
+            }
+            Operator::Eq | Operator::Ne => Predicate::Compare(operator, self.parse_literal(field, field_type, &value_token, &value)?),
+            _ => match (field_type, self.parse_literal(field, field_type, &value_token, &value)?) {
+                (FieldType::Number, literal) | (FieldType::Address, literal @ Literal::Address(_)) => Predicate::Compare(operator, literal),
+                (FieldType::Address, _) => return self.error_at(&value_token, "expected an IP address"),
+                _ => return self.error_at(&operator_token, &format!("'{}' can only be compared with '=', '!=', '~', '!~' or 'in'", field))
+            }
+        };
+        Ok(Expr::Comparison(field, predicate))
+    }
+
+    // A parenthesized list of values and ranges, or a single range ('port in 8000..8100')
+    fn parse_items(&mut self, field: &str, field_type: FieldType) -> Result<Vec<Item>, WhereError> {
+        if self.peek().kind != TokenKind::LeftParen {
+            return Ok(vec![self.parse_item(field, field_type)?])
+        }
+        self.next();
+        let mut items = vec![self.parse_item(field, field_type)?];
+        while self.peek().kind == TokenKind::Comma {
+            self.next();
+            items.push(self.parse_item(field, field_type)?);
+        }
+        self.expect_right_paren()?;
+        Ok(items)
+    }
+
+    fn parse_item(&mut self, field: &str, field_type: FieldType) -> Result<Item, WhereError> {
+        let token = self.next();
+        match &token.kind {
+            TokenKind::Word(value) => match value.split_once("..") {
+                Some((min, max)) => {
+                    if !matches!(field_type, FieldType::Number | FieldType::Address) {
+                        return self.error_at(&token, &format!("ranges need a number or address field, '{}' is not one", field))
+                    }
+                    if min.is_empty() && max.is_empty() {
+                        return self.error_at(&token, "a range needs at least one bound")
+                    }
+                    let bound = |value: &str| {
+                        if value.is_empty() {
+                            return Ok(None)
+                        }
+                        match self.parse_literal(field, field_type, &token, value)? {
+                            literal @ (Literal::Number(_) | Literal::Address(_)) => Ok(Some(literal)),
+                            _ => self.error_at(&token, "expected an IP address range")
+                        }
+                    };
+                    Ok(Item::Range(bound(min)?, bound(max)?))
+                }
+                None => Ok(Item::Single(self.parse_literal(field, field_type, &token, value)?))
+            },
+            TokenKind::Quoted(value) => Ok(Item::Single(self.parse_literal(field, field_type, &token, value)?)),
+            _ => self.error_at(&token, &format!("expected a value, found {}", self.describe(&token)))
+        }
+    }
+
+    // Text is compared case-insensitively, so it is lowercased once here. Addresses can be CIDR prefixes ('10.0.0.0/8'), and those that aren't IPs ('*', Unix paths) are compared as text
+    fn parse_literal(&self, field: &str, field_type: FieldType, token: &Token, value: &str) -> Result<Literal, WhereError> {
+        match field_type {
+            FieldType::Number => match value.parse() {
+                Ok(number) => Ok(Literal::Number(number)),
+                Err(_) => self.error_at(token, &format!("expected a number for '{}'", field))
+            },
+            FieldType::Address => match (value.parse(), value.contains('/')) {
+                (Ok(address), _) => Ok(Literal::Address(address)),
+                (Err(_), true) if !value.starts_with('/') => match AddressPrefix::parse(value) {
+                    Some(prefix) => Ok(Literal::Prefix(prefix)),
+                    None => self.error_at(token, &format!("invalid CIDR prefix for '{}'", field))
+                },
+                (Err(_), _) => Ok(Literal::Text(value.to_lowercase()))
+            },
+            FieldType::Text => Ok(Literal::Text(value.to_lowercase()))
+        }
+    }
+}
+
+fn equals(value: &FieldValue, literal: &Literal) -> bool {
+    match (value, literal) {
+        (FieldValue::Number(a), Literal::Number(b)) => a == b,
+        (FieldValue::Text(a), Literal::Address(b)) => a.parse::<IpAddr>().is_ok_and(|a| a == *b),
+        (FieldValue::Text(a), Literal::Prefix(prefix)) => a.parse::<IpAddr>().is_ok_and(|a| prefix.contains(a)),
+        (FieldValue::Text(a), Literal::Text(b)) => a.to_lowercase() == *b,
+        _ => false
+    }
+}
+
+// Addresses are only ordered within the same IP version
+fn compare(value: &FieldValue, literal: &Literal) -> Option<Ordering> {
+    match (value, literal) {
+        (FieldValue::Number(a), Literal::Number(b)) => Some(a.cmp(b)),
+        (FieldValue::Text(a), Literal::Address(b)) => match (a.parse::<IpAddr>().ok()?, b) {
+            (a @ IpAddr::V4(_), IpAddr::V4(_)) | (a @ IpAddr::V6(_), IpAddr::V6(_)) => Some(a.cmp(b)),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+impl Item {
+    fn contains(&self, value: &FieldValue) -> bool {
+        match self {
+            Item::Single(literal) => equals(value, literal),
+            Item::Range(min, max) => {
+                min.as_ref().is_none_or(|min| compare(value, min).is_some_and(Ordering::is_ge)) &&
+                max.as_ref().is_none_or(|max| compare(value, max).is_some_and(Ordering::is_le))
+            }
+        }
+    }
+}
+
+impl Predicate {
+    fn matches(&self, value: &FieldValue) -> bool {
+        match self {
+            Predicate::Compare(Operator::Eq, literal) => equals(value, literal),
+            Predicate::Compare(Operator::Ne, literal) => !equals(value, literal),
+            Predicate::Compare(operator, literal) => compare(value, literal).is_some_and(|ordering| match operator {
+                Operator::Lt => ordering.is_lt(),
+                Operator::Le => ordering.is_le(),
+                Operator::Gt => ordering.is_gt(),
+                _ => ordering.is_ge()
+            }),
+            Predicate::Regex(re, negated) => match value {
+                FieldValue::Text(text) => re.is_match(text) != *negated,
+                _ => false
+            },
+            Predicate::In(items, negated) => items.iter().any(|item| item.contains(value)) != *negated
+        }
+    }
+}
+
+// Parsed and type-checked once, errors point at the offending token of the expression
+pub struct WhereFilter {
+    expr: Expr
+}
+
+impl WhereFilter {
+    pub fn new(expression: &str) -> Self {
+        Self::parse(expression).unwrap_or_else(|e| RpdError::InvalidWhereErr(expression.to_string(), e.start, e.len, e.message).handle())
+    }
+
+    pub fn parse(expression: &str) -> Result<Self, WhereError> {
+        let mut parser = Parser { expression, tokens: tokenize(expression)?, position: 0 };
+        if parser.peek().kind == TokenKind::End {
+            return Err(WhereError::new(0, 1, "empty expression"))
+        }
+        let expr = parser.parse_or()?;
+        if parser.peek().kind != TokenKind::End {
+            return parser.error_at(parser.peek(), &format!("expected 'and', 'or' or end of expression, found {}", parser.describe(parser.peek())))
+        }
+        Ok(WhereFilter { expr })
+    }
+
+    pub fn matches(&self, socket: &Socket) -> bool {
+        Self::evaluate(&self.expr, socket)
+    }
+
+    // A comparison on a field the socket has no value for is false, negated operators included
+    fn evaluate(expr: &Expr, socket: &Socket) -> bool {
+        match expr {
+            Expr::And(left, right) => Self::evaluate(left, socket) && Self::evaluate(right, socket),
+            Expr::Or(left, right) => Self::evaluate(left, socket) || Self::evaluate(right, socket),
+            Expr::Not(inner) => !Self::evaluate(inner, socket),
+            Expr::Comparison(field, predicate) => get_field_value(socket, field).is_some_and(|value| predicate.matches(&value))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::socket_source::FixtureSource;
+
+    // (PID, local port) of the fixture rows matching the expression, in table order
+    fn matching(expression: &str) -> Vec<(u32, u16)> {
+        let Ok(filter) = WhereFilter::parse(expression) else { panic!("'{}' should parse", expression) };
+        FixtureSource::default().sockets.iter().filter(|s| filter.matches(s)).map(|s| (s.pid, s.port)).collect()
+    }
+
+    fn error(expression: &str) -> WhereError {
+        let Err(error) = WhereFilter::parse(expression) else { panic!("'{}' should not parse", expression) };
+        error
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(matching("process-name = postgres or process-name = python and port = 49822"), vec![(2300, 5432), (3100, 49822), (2300, 0)]);
+        assert_eq!(matching("(process-name = postgres or process-name = python) and port = 49822"), vec![(3100, 49822)]);
+        assert_eq!(matching("port = 22 or port = 445 or port = 123"), vec![(4, 445), (1, 22), (710, 123)]);
+    }
+
+    #[test]
+    fn not_binds_tighter_than_and() {
+        assert_eq!(matching("not port = 443 and process-name = nginx"), vec![(1200, 80), (1200, 80)]);
+        assert_eq!(matching("not (port = 443 and process-name = nginx) and port = 443"), Vec::<(u32, u16)>::new());
+        assert_eq!(matching("not not port = 22"), vec![(1, 22)]);
+    }
+
+    #[test]
+    fn parentheses_group_expressions() {
+        assert_eq!(matching("port = 443 and (state = time_wait or state = close_wait)"), vec![(1201, 443)]);
+        assert_eq!(matching("((pid = 5200))"), vec![(5200, 8080)]);
+    }
+
+    #[test]
+    fn in_takes_lists_and_ranges() {
+        assert_eq!(matching("port in (22, 445, 5000..6000)"), vec![(4, 445), (1, 22), (2300, 5432), (720, 5353)]);
+        assert_eq!(matching("uptime in ..10"), vec![(3100, 49900), (4100, 0)]);
+        assert_eq!(matching("remote-address in 10.0.0.0..10.255.255.255"), vec![(5200, 8080)]);
+        assert_eq!(matching("remote-address in (203.0.113.0/24, 93.184.216.34)"), vec![(1201, 443), (3100, 49830)]);
+        assert_eq!(matching("process-name in (Chrony, \"systemd-resolved\")"), vec![(640, 53), (710, 123)]);
+        // UDP sockets have no state, so they match neither 'in' nor 'not in'
+        assert_eq!(matching("state not in (listen, established, unconnected)"), vec![(1201, 443), (3100, 49830)]);
+    }
+
+    #[test]
+    fn text_comparisons_ignore_case() {
+        assert_eq!(matching("state = Time_Wait"), vec![(1201, 443)]);
+        assert_eq!(matching("process-name ~ ^NGINX$ and port != 80"), vec![(1200, 443), (1201, 443), (1201, 443)]);
+        assert_eq!(matching("user ~ ALICE and protocol !~ tcp"), vec![(3100, 0), (4100, 0)]);
+    }
+
+    #[test]
+    fn compares_numbers_and_addresses() {
+        assert_eq!(matching("recv-q > 0"), vec![(3100, 49830)]);
+        assert_eq!(matching("local-address >= 10.244.0.0 and local-address < 127.0.0.0"), vec![(5200, 8080)]);
+        assert_eq!(matching("local-address = 0:0:0:0:0:0:0:1"), vec![(3100, 49900)]);
+        assert_eq!(matching("local-address = 10.244.0.0/16"), vec![(5200, 8080)]);
+    }
+
+    #[test]
+    fn matches_the_bound_of_estimated_uptimes() {
+        let Ok(filter) = WhereFilter::parse("uptime-bound = lower") else { panic!("should parse") };
+        let estimated = |bound: &str| Socket { uptime_bound: Some(bound.to_string()), ..Default::default() };
+        assert!(filter.matches(&estimated("lower")));
+        assert!(!filter.matches(&estimated("upper")));
+        // Exact uptimes have no bound
+        assert!(!filter.matches(&Socket::default()));
+    }
+
+    #[test]
+    fn reports_unknown_fields_and_bad_literals() {
+        assert_eq!(error("colour = red"), WhereError::new(0, 6, "unknown field 'colour'"));
+        assert_eq!(error("port = http"), WhereError::new(7, 4, "expected a number for 'port'"));
+        assert_eq!(error("process-name < 3"), WhereError::new(13, 1, "'process-name' can only be compared with '=', '!=', '~', '!~' or 'in'"));
+        assert_eq!(error("pid ~ 1"), WhereError::new(4, 1, "'~' and '!~' need a text field, 'pid' is not one"));
+        assert_eq!(error("state in a..b"), WhereError::new(9, 4, "ranges need a number or address field, 'state' is not one"));
+        assert_eq!(error("process-name ~ '('"), WhereError::new(15, 3, "invalid regular expression"));
+        assert_eq!(error("local-address = 10.0.0.0/33"), WhereError::new(16, 11, "invalid CIDR prefix for 'local-address'"));
+    }
+
+    #[test]
+    fn reports_unterminated_input() {
+        assert_eq!(error(""), WhereError::new(0, 1, "empty expression"));
+        assert_eq!(error("process-name = \"nginx"), WhereError::new(15, 6, "unterminated string"));
+        assert_eq!(error("(port = 80"), WhereError::new(10, 1, "expected ')', found end of expression"));
+        assert_eq!(error("port ="), WhereError::new(6, 1, "expected a value, found end of expression"));
+        assert_eq!(error("port = 80 and"), WhereError::new(13, 1, "expected a field name, found end of expression"));
+        assert_eq!(error("port in (80, 443"), WhereError::new(16, 1, "expected ')', found end of expression"));
+        assert_eq!(error("port = 80 port = 81"), WhereError::new(10, 4, "expected 'and', 'or' or end of expression, found 'port'"));
+        assert_eq!(error("port ! 80"), WhereError::new(5, 1, "expected '!=' or '!~', use 'not' to negate an expression"));
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use serde_json::Value;
+use crate::tools::{input::json_to_row, rpderror::RpdError, socket::Socket};
+
+// State of the responder and of the originator for each Zeek 'conn_state' value, UNKNOWN when it doesn't tell
+fn map_conn_state(conn_state: &str) -> (&'static str, &'static str) {
+    match conn_state {
+        // Connection attempt seen, no reply
+        "S0" => ("SYN_RCVD", "SYN_SENT"),
+        // Established, not terminated
+        "S1" => ("ESTABLISHED", "ESTABLISHED"),
+        // Established, close attempt seen from the originator only (S2) or from the responder only (S3)
+        "S2" => ("CLOSE_WAIT", "FIN_WAIT1"),
+        "S3" => ("FIN_WAIT1", "CLOSE_WAIT"),
+        // Normal termination, rejected, reset by either side, or half open connections that got closed
+        "SF" | "REJ" | "RSTO" | "RSTR" | "RSTOS0" | "RSTRH" | "SH" | "SHR" => ("CLOSED", "CLOSED"),
+        _ => ("UNKNOWN", "UNKNOWN")
+    }
+}
+
+// Unset fields are written as '-' in TSV logs and left out of JSON logs
+fn get_field<'a>(row: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    row.get(key).map(String::as_str).filter(|v| *v != "-" && *v != "(empty)")
+}
+
+fn get_number<T: std::str::FromStr>(row: &HashMap<String, String>, key: &str) -> Option<T> {
+    get_field(row, key).and_then(|v| v.parse().ok())
+}
+
+// The responder is the local end unless the log marks only the originator as local through 'local_orig' and 'local_resp'
+fn build_socket(row: &HashMap<String, String>) -> Option<Socket> {
+    let protocol = match get_field(row, "proto")? {
+        "tcp" => "TCP",
+        "udp" => "UDP",
+        _ => return None
+    };
+    let is_local = |key: &str| matches!(get_field(row, key), Some("T" | "true"));
+    let originator_is_local = is_local("local_orig") && !is_local("local_resp");
+    let originator = (get_field(row, "id.orig_h")?, get_number::<u16>(row, "id.orig_p")?);
+    let responder = (get_field(row, "id.resp_h")?, get_number::<u16>(row, "id.resp_p")?);
+    let (local, remote) = if originator_is_local { (originator, responder) } else { (responder, originator) };
+    let (responder_state, originator_state) = map_conn_state(get_field(row, "conn_state").unwrap_or_default());
+    let state = match (protocol, originator_is_local) {
+        ("UDP", _) => " ",
+        (_, true) => originator_state,
+        (_, false) => responder_state
+    };
+    Some(Socket {
+        process_name: "unknown".to_string(),
+        pid: 0,
+        port: local.1,
+        protocol,
+        local_addr: local.0.to_string(),
+        remote_addr: Some(remote.0.to_string()),
+        remote_port: Some(remote.1),
+        state: state.to_string(),
+        executable_path: None,
+        // Seconds with microsecond precision, unset for connections with a single packet
+        uptime: get_number::<f64>(row, "duration").map_or(0, |d| d as u64),
+        orig_bytes: get_number(row, "orig_bytes"),
+        resp_bytes: get_number(row, "resp_bytes"),
+        ..Default::default()
+    })
+}
+
+// '\x09' style escapes are used by the '#separator' header
+fn unescape_separator(value: &str) -> String {
+    match value.strip_prefix("\\x").and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+        Some(byte) => (byte as char).to_string(),
+        None => value.to_string()
+    }
+}
+
+// '#separator', '#fields' and '#types' headers followed by one row per line, '#close' ends the log
+fn tsv_to_rows(input: &str, content: &str) -> Vec<HashMap<String, String>> {
+    let mut separator = "\t".to_string();
+    let mut fields: Option<Vec<String>> = None;
+    let mut rows: Vec<HashMap<String, String>> = Vec::new();
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        if let Some(header) = line.strip_prefix('#') {
+            if let Some(value) = header.strip_prefix("separator ") {
+                separator = unescape_separator(value.trim());
+            } else if let Some(value) = header.strip_prefix("fields") {
+                fields = Some(value.split(separator.as_str()).filter(|f| !f.is_empty()).map(str::to_string).collect());
+            }
+            continue
+        }
+        let Some(fields) = &fields else {
+            RpdError::ParseInputErr(input.to_string(), "missing '#fields' header".to_string()).handle()
+        };
+        rows.push(fields.iter().cloned().zip(line.split(separator.as_str()).map(str::to_string)).collect());
+    }
+    rows
+}
+
+// One JSON object per line as written with 'LogAscii::use_json', or a single array as exported by other tools
+fn json_to_rows(input: &str, content: &str) -> Vec<HashMap<String, String>> {
+    let values: Vec<Value> = if content.starts_with('[') {
+        match serde_json::from_str::<Value>(content) {
+            Ok(Value::Array(values)) => values,
+            Ok(_) => Vec::new(),
+            Err(e) => RpdError::ParseInputErr(input.to_string(), e.to_string()).handle()
+        }
+    } else {
+        content.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<Value>(line).unwrap_or_else(|e| RpdError::ParseInputErr(input.to_string(), e.to_string()).handle()))
+            .collect()
+    };
+    values.iter().filter_map(Value::as_object).map(json_to_row).collect()
+}
+
+// Zeek 'conn.log' in its default TSV layout or as JSON, only TCP and UDP connections are kept
+pub fn parse_zeek(input: &str, content: &str) -> Vec<Socket> {
+    let trimmed = content.trim_start();
+    let rows = if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        json_to_rows(input, trimmed)
+    } else {
+        tsv_to_rows(input, content)
+    };
+    rows.iter().filter_map(build_socket).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::input::tests::rows;
+
+    const CONN_TSV: &str = "#separator \\x09
+#set_separator\t,
+#empty_field\t(empty)
+#unset_field\t-
+#path\tconn
+#fields\tts\tuid\tid.orig_h\tid.orig_p\tid.resp_h\tid.resp_p\tproto\tservice\tduration\torig_bytes\tresp_bytes\tconn_state\tlocal_orig\tlocal_resp
+#types\ttime\tstring\taddr\tport\taddr\tport\tenum\tstring\tinterval\tcount\tcount\tstring\tbool\tbool
+1709288130.123456\tCAbc\t192.0.2.10\t50000\t10.0.0.5\t443\ttcp\tssl\t61.532871\t1520\t48211\tSF\tF\tT
+1709288131.000000\tCDef\t10.0.0.5\t53000\t8.8.8.8\t53\tudp\tdns\t0.012000\t40\t120\tSF\tT\tF
+1709288132.000000\tCGhi\t10.0.0.5\t41000\t203.0.113.10\t22\ttcp\t-\t-\t-\t-\tS0\tT\tF
+1709288133.000000\tCJkl\t192.0.2.11\t50001\t10.0.0.5\t8080\ttcp\thttp\t3600.500000\t300\t1000\tS2\t-\t-
+1709288134.000000\tCMno\t10.0.0.5\t8\t10.0.0.1\t0\ticmp\t-\t0.001000\t56\t56\tOTH\tT\tF
+#close\t2024-03-01-10-15-34
+";
+
+    // Same log written with 'LogAscii::use_json', unset fields are left out
+    const CONN_JSON: &str = r#"{"ts":1709288130.123456,"uid":"CAbc","id.orig_h":"192.0.2.10","id.orig_p":50000,"id.resp_h":"10.0.0.5","id.resp_p":443,"proto":"tcp","service":"ssl","duration":61.532871,"orig_bytes":1520,"resp_bytes":48211,"conn_state":"SF","local_orig":false,"local_resp":true}
+{"ts":1709288131.0,"uid":"CDef","id.orig_h":"10.0.0.5","id.orig_p":53000,"id.resp_h":"8.8.8.8","id.resp_p":53,"proto":"udp","service":"dns","duration":0.012,"orig_bytes":40,"resp_bytes":120,"conn_state":"SF","local_orig":true,"local_resp":false}
+{"ts":1709288132.0,"uid":"CGhi","id.orig_h":"10.0.0.5","id.orig_p":41000,"id.resp_h":"203.0.113.10","id.resp_p":22,"proto":"tcp","conn_state":"S0","local_orig":true,"local_resp":false}
+{"ts":1709288133.0,"uid":"CJkl","id.orig_h":"192.0.2.11","id.orig_p":50001,"id.resp_h":"10.0.0.5","id.resp_p":8080,"proto":"tcp","service":"http","duration":3600.5,"orig_bytes":300,"resp_bytes":1000,"conn_state":"S2"}
+{"ts":1709288134.0,"uid":"CMno","id.orig_h":"10.0.0.5","id.orig_p":8,"id.resp_h":"10.0.0.1","id.resp_p":0,"proto":"icmp","duration":0.001,"orig_bytes":56,"resp_bytes":56,"conn_state":"OTH","local_orig":true,"local_resp":false}
+"#;
+
+    fn assert_conn_log(sockets: &[Socket]) {
+        assert_eq!(rows(sockets), vec![
+            (0, "unknown", "TCP", "10.0.0.5", 443, Some("192.0.2.10"), Some(50000), "CLOSED"),
+            (0, "unknown", "UDP", "10.0.0.5", 53000, Some("8.8.8.8"), Some(53), " "),
+            (0, "unknown", "TCP", "10.0.0.5", 41000, Some("203.0.113.10"), Some(22), "SYN_SENT"),
+            (0, "unknown", "TCP", "10.0.0.5", 8080, Some("192.0.2.11"), Some(50001), "CLOSE_WAIT")
+        ]);
+        assert_eq!(sockets.iter().map(|s| s.uptime).collect::<Vec<_>>(), vec![61, 0, 0, 3600]);
+        assert_eq!(sockets.iter().map(|s| (s.orig_bytes, s.resp_bytes)).collect::<Vec<_>>(), vec![
+            (Some(1520), Some(48211)),
+            (Some(40), Some(120)),
+            (None, None),
+            (Some(300), Some(1000))
+        ]);
+    }
+
+    #[test]
+    fn parses_tsv_logs() {
+        assert_conn_log(&parse_zeek("conn.log", CONN_TSV));
+    }
+
+    #[test]
+    fn parses_json_lines_and_arrays() {
+        assert_conn_log(&parse_zeek("conn.log", CONN_JSON));
+        let array = format!("[{}]", CONN_JSON.trim().lines().collect::<Vec<_>>().join(","));
+        assert_conn_log(&parse_zeek("conn.json", &array));
+    }
+
+    #[test]
+    fn reads_custom_separators() {
+        let log = "#separator ,\n#fields,id.orig_h,id.orig_p,id.resp_h,id.resp_p,proto,conn_state\n192.0.2.10,50000,10.0.0.5,443,tcp,S1\n";
+        assert_eq!(rows(&parse_zeek("conn.log", log)), vec![(0, "unknown", "TCP", "10.0.0.5", 443, Some("192.0.2.10"), Some(50000), "ESTABLISHED")]);
+    }
+
+    #[test]
+    fn maps_conn_states_for_both_ends() {
+        assert_eq!(map_conn_state("S0"), ("SYN_RCVD", "SYN_SENT"));
+        assert_eq!(map_conn_state("S1"), ("ESTABLISHED", "ESTABLISHED"));
+        assert_eq!(map_conn_state("S2"), ("CLOSE_WAIT", "FIN_WAIT1"));
+        assert_eq!(map_conn_state("S3"), ("FIN_WAIT1", "CLOSE_WAIT"));
+        for closed in ["SF", "REJ", "RSTO", "RSTR", "RSTOS0", "RSTRH", "SH", "SHR"] {
+            assert_eq!(map_conn_state(closed), ("CLOSED", "CLOSED"));
+        }
+        assert_eq!(map_conn_state("OTH"), ("UNKNOWN", "UNKNOWN"));
+    }
+}